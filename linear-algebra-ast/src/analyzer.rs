@@ -0,0 +1,706 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::ast::expression::Binary;
+use crate::ast::expression::Call;
+use crate::ast::expression::Expression;
+use crate::ast::expression::ExpressionType;
+use crate::ast::expression::ExpressionVisitor;
+use crate::ast::expression::Grouping;
+use crate::ast::expression::Identifier;
+use crate::ast::expression::Lambda;
+use crate::ast::expression::Literal;
+use crate::ast::expression::Logical;
+use crate::ast::expression::Unary;
+use crate::ast::statement::AssignStatement;
+use crate::ast::statement::ExplainStatement;
+use crate::ast::statement::ExpressionStatement;
+use crate::ast::statement::ForStatement;
+use crate::ast::statement::FunctionStatement;
+use crate::ast::statement::IfStatement;
+use crate::ast::statement::LetStatement;
+use crate::ast::statement::NewLineStatement;
+use crate::ast::statement::PrintStatement;
+use crate::ast::statement::ReturnStatement;
+use crate::ast::statement::SetStatement;
+use crate::ast::statement::Statement;
+use crate::ast::statement::StatementType;
+use crate::ast::statement::StatementVisitor;
+use crate::ast::statement::WhileStatement;
+use crate::builtins::BuiltinRegistry;
+use crate::environment::Environment;
+use crate::error::Diagnostic;
+use crate::format;
+use crate::tokens::Token;
+use crate::tokens::TokenType;
+use crate::value::Value;
+use crate::value::ValueType;
+
+/// What the analyzer knows about a value without running the program.
+#[derive(Clone, PartialEq)]
+pub enum Shape {
+    Scalar,
+    Bool,
+    String,
+    Matrix(usize, usize),
+    /// a matrix whose size depends on its values, like the roots of a polynomial
+    AnyMatrix,
+    Tuple(Vec<Shape>),
+    /// a function taking this many arguments
+    Function(usize),
+    /// the builtin with this name
+    Builtin(String),
+    /// nothing is known, usually because an error was already reported
+    Unknown,
+}
+
+impl Shape {
+    pub(crate) fn of(value: &Value) -> Shape {
+        match &value.data {
+            ValueType::SCALAR(_) => Shape::Scalar,
+            ValueType::MATRIX(m) => Shape::Matrix(m.rows, m.cols),
+            ValueType::TUPLE(values) => Shape::Tuple(values.iter().map(Shape::of).collect()),
+            ValueType::FUNCTION(f) => Shape::Function(f.arity()),
+            ValueType::BUILTIN(b) => Shape::Builtin(b.name.clone()),
+            ValueType::BOOL(_) => Shape::Bool,
+            ValueType::STRING(_) => Shape::String,
+        }
+    }
+
+    /// Whether this could still turn out to be a scalar.
+    pub(crate) fn maybe_scalar(&self) -> bool {
+        matches!(self, Shape::Scalar | Shape::Unknown)
+    }
+
+    pub(crate) fn maybe_bool(&self) -> bool {
+        matches!(self, Shape::Bool | Shape::Unknown)
+    }
+
+    pub(crate) fn maybe_matrix(&self) -> bool {
+        matches!(
+            self,
+            Shape::Matrix(_, _) | Shape::AnyMatrix | Shape::Unknown
+        )
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Shape::Scalar => write!(f, "a scalar"),
+            Shape::Bool => write!(f, "a boolean"),
+            Shape::String => write!(f, "a string"),
+            Shape::Matrix(rows, cols) => write!(f, "{}×{}", rows, cols),
+            Shape::AnyMatrix => write!(f, "a matrix"),
+            Shape::Tuple(shapes) => write!(f, "a tuple of {}", shapes.len()),
+            Shape::Function(_) | Shape::Builtin(_) => write!(f, "a function"),
+            Shape::Unknown => write!(f, "a value"),
+        }
+    }
+}
+
+/// Checks that scalars and matrices are combined with compatible shapes
+/// before a program runs, so a mistake on the last line of a long script is
+/// reported without doing all of the work before it. Anything that depends
+/// on values rather than shapes, like dividing by zero, is still left to the
+/// interpreter.
+pub struct Analyzer<'a> {
+    /// variables defined before this program, e.g. on earlier REPL lines
+    globals: &'a Environment,
+    builtins: &'a BuiltinRegistry,
+    /// innermost scope last, the first is the program's top level
+    scopes: Vec<HashMap<String, Shape>>,
+    /// inside a function body a name may be a global defined after the
+    /// function, so unknown names are left for the interpreter
+    function_depth: usize,
+    /// the first scope of the innermost function body. Variables outside it
+    /// can change before the function is called, so their shapes are unknown.
+    function_scope: usize,
+    errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+    /// warn when a let reuses a name that is already defined
+    warn_shadowing: bool,
+    /// names assigned inside any function body, which could be anything
+    /// after a function is called
+    assigned_in_functions: HashSet<String>,
+}
+
+impl<'a> Analyzer<'a> {
+    pub fn new(globals: &'a Environment, builtins: &'a BuiltinRegistry) -> Self {
+        // functions from earlier REPL lines can change variables too
+        let mut assigned_in_functions = HashSet::new();
+        for value in globals.values() {
+            if let ValueType::FUNCTION(function) = &value.data {
+                assigned_names(&function.body, &mut assigned_in_functions);
+            }
+        }
+        Analyzer {
+            globals,
+            builtins,
+            scopes: vec![HashMap::new()],
+            function_depth: 0,
+            function_scope: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            warn_shadowing: false,
+            assigned_in_functions,
+        }
+    }
+
+    pub fn warn_shadowing(mut self, warn_shadowing: bool) -> Self {
+        self.warn_shadowing = warn_shadowing;
+        self
+    }
+
+    /// Checks every statement, returning all errors found if there were any.
+    /// Warnings are left in `warnings` either way.
+    pub fn analyze(&mut self, statements: &[Statement]) -> Result<(), Vec<Diagnostic>> {
+        for statement in statements {
+            statement.visit(self);
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) -> Shape {
+        self.errors.push(Diagnostic::shape(token, message));
+        Shape::Unknown
+    }
+
+    fn define(&mut self, name: &Token, shape: Shape) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.lexeme.to_string(), shape);
+    }
+
+    /// Checks the body of an if, while or for in a nested `scope`, returning
+    /// the shapes it leaves behind in the enclosing scopes and restoring them
+    /// to what they were.
+    fn branch(
+        &mut self,
+        scope: HashMap<String, Shape>,
+        statements: &[Statement],
+    ) -> Vec<HashMap<String, Shape>> {
+        let before = self.scopes.clone();
+        self.scopes.push(scope);
+        for statement in statements {
+            statement.visit(self);
+        }
+        self.scopes.pop();
+        std::mem::replace(&mut self.scopes, before)
+    }
+
+    /// The shapes known after one of two branches ran, a name defined
+    /// differently by each could be either.
+    fn merge(
+        a: Vec<HashMap<String, Shape>>,
+        b: Vec<HashMap<String, Shape>>,
+    ) -> Vec<HashMap<String, Shape>> {
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| Analyzer::merge_scope(a, b))
+            .collect()
+    }
+
+    fn merge_scope(
+        a: HashMap<String, Shape>,
+        mut b: HashMap<String, Shape>,
+    ) -> HashMap<String, Shape> {
+        let mut merged = HashMap::new();
+        for (name, shape) in a {
+            let other = b.remove(&name).unwrap_or(Shape::Unknown);
+            merged.insert(
+                name,
+                if shape == other {
+                    shape
+                } else {
+                    Shape::Unknown
+                },
+            );
+        }
+        for name in b.into_keys() {
+            merged.insert(name, Shape::Unknown);
+        }
+        merged
+    }
+
+    /// A loop body may run any number of times, so it is checked against the
+    /// shapes known after every number of runs. Those are found first without
+    /// reporting anything, each pass can only make more names unknown.
+    fn loop_body(&mut self, scope: HashMap<String, Shape>, statements: &[Statement]) {
+        let errors = self.errors.len();
+        let warnings = self.warnings.len();
+        loop {
+            let before = self.scopes.clone();
+            let after = self.branch(scope.clone(), statements);
+            let merged = Analyzer::merge(before.clone(), after);
+            if merged == before {
+                break;
+            }
+            self.scopes = merged;
+        }
+        self.errors.truncate(errors);
+        self.warnings.truncate(warnings);
+        let after = self.branch(scope, statements);
+        let before = std::mem::take(&mut self.scopes);
+        self.scopes = Analyzer::merge(before, after);
+    }
+
+    fn condition(&mut self, token: &Token, condition: &Expression) {
+        let shape = condition.visit(self);
+        if !shape.maybe_bool() {
+            self.error(
+                token,
+                &format!("Condition must be a boolean, not {}", shape),
+            );
+        }
+    }
+
+    /// Checks a function body in its own scope, where parameters could be anything.
+    fn function_body(&mut self, params: &[Token], body: &[Statement]) {
+        let mut scope = HashMap::new();
+        for param in params {
+            scope.insert(param.lexeme.to_string(), Shape::Unknown);
+        }
+        let outer = std::mem::replace(&mut self.function_scope, self.scopes.len());
+        self.scopes.push(scope);
+        self.function_depth += 1;
+        for statement in body {
+            statement.visit(self);
+        }
+        self.function_depth -= 1;
+        self.scopes.pop();
+        self.function_scope = outer;
+    }
+
+    /// After a call that may run a function, the variables any function body
+    /// assigns could hold anything.
+    fn forget_assigned(&mut self) {
+        for name in &self.assigned_in_functions {
+            match self
+                .scopes
+                .iter_mut()
+                .rev()
+                .find(|scope| scope.contains_key(name))
+            {
+                Some(scope) => {
+                    scope.insert(name.clone(), Shape::Unknown);
+                }
+                None if self.globals.lookup(name).is_some() => {
+                    self.scopes[0].insert(name.clone(), Shape::Unknown);
+                }
+                None => (),
+            }
+        }
+    }
+
+    /// Operators only apply to scalars and matrices.
+    fn check_operand(&mut self, operator: &Token, shape: &Shape) -> bool {
+        let message = match shape {
+            Shape::Tuple(_) => "Cannot apply an operator to a tuple, unpack it with let first.",
+            Shape::Function(_) | Shape::Builtin(_) => {
+                "Cannot apply an operator to a function, call it first."
+            }
+            Shape::Bool => "Cannot apply an operator to a boolean.",
+            Shape::String => "Cannot apply an operator to a string.",
+            _ => return true,
+        };
+        self.error(operator, message);
+        false
+    }
+}
+
+impl ExpressionVisitor<Shape> for Analyzer<'_> {
+    fn visit_literal(&mut self, literal: &Literal) -> Shape {
+        Shape::of(&literal.value)
+    }
+
+    fn visit_identifier(&mut self, identifier: &Identifier) -> Shape {
+        let name = &identifier.name.lexeme;
+        for scope in self.scopes[self.function_scope..].iter().rev() {
+            if let Some(shape) = scope.get(name) {
+                return shape.clone();
+            }
+        }
+        if self.function_depth > 0 {
+            return Shape::Unknown;
+        }
+        match self.globals.lookup(name) {
+            Some(value) => Shape::of(&value),
+            None if self.builtins.get(name).is_some() => Shape::Builtin(name.to_string()),
+            None => self.error(&identifier.name, "Undefined variable."),
+        }
+    }
+
+    fn visit_unary(&mut self, unary: &Unary) -> Shape {
+        let right = unary.right.visit(self);
+        let operator = &unary.operator;
+        if operator.token_type == TokenType::TOKEN_BANG {
+            return match right {
+                Shape::Bool | Shape::Unknown => Shape::Bool,
+                _ => self.error(
+                    operator,
+                    &format!("Expected a boolean after !, not {}", right),
+                ),
+            };
+        }
+        if right == Shape::Unknown || !self.check_operand(operator, &right) {
+            return Shape::Unknown;
+        }
+        match operator.token_type {
+            TokenType::TOKEN_MINUS => right,
+            TokenType::TOKEN_TRANSPOSE => match right {
+                Shape::Scalar => self.error(operator, "Cannot transpose a scalar."),
+                Shape::Matrix(rows, cols) => Shape::Matrix(cols, rows),
+                _ => right,
+            },
+            _ => Shape::Unknown,
+        }
+    }
+
+    fn visit_binary(&mut self, binary: &Binary) -> Shape {
+        let left = binary.left.visit(self);
+        let right = binary.right.visit(self);
+        let operator = &binary.operator;
+        match operator.token_type {
+            TokenType::TOKEN_EQUAL_EQUAL | TokenType::TOKEN_BANG_EQUAL => {
+                for shape in [&left, &right] {
+                    if let Shape::Tuple(_) | Shape::Function(_) | Shape::Builtin(_) = shape {
+                        return self.error(operator, &format!("Cannot compare {}", shape));
+                    }
+                }
+                return Shape::Bool;
+            }
+            TokenType::TOKEN_LESS
+            | TokenType::TOKEN_LESS_EQUAL
+            | TokenType::TOKEN_GREATER
+            | TokenType::TOKEN_GREATER_EQUAL => {
+                if !left.maybe_scalar() || !right.maybe_scalar() {
+                    self.error(
+                        operator,
+                        &format!("Only scalars can be ordered, not {} and {}", left, right),
+                    );
+                }
+                return Shape::Bool;
+            }
+            _ => (),
+        }
+        if !self.check_operand(operator, &left) || !self.check_operand(operator, &right) {
+            return Shape::Unknown;
+        }
+        if left == Shape::Unknown || right == Shape::Unknown {
+            return Shape::Unknown;
+        }
+        match operator.token_type {
+            TokenType::TOKEN_PLUS | TokenType::TOKEN_MINUS => {
+                let action = if operator.token_type == TokenType::TOKEN_PLUS {
+                    "add"
+                } else {
+                    "subtract"
+                };
+                match (&left, &right) {
+                    (Shape::Scalar, Shape::Scalar) => Shape::Scalar,
+                    (Shape::Scalar, _) | (_, Shape::Scalar) => self.error(
+                        operator,
+                        &format!("Cannot {} {} and {}", action, left, right),
+                    ),
+                    (Shape::Matrix(r1, c1), Shape::Matrix(r2, c2)) if r1 != r2 || c1 != c2 => self
+                        .error(
+                            operator,
+                            &format!("Cannot {} {} and {}", action, left, right),
+                        ),
+                    (Shape::Matrix(_, _), _) => left,
+                    _ => right,
+                }
+            }
+            TokenType::TOKEN_STAR => match (&left, &right) {
+                (Shape::Scalar, _) => right,
+                (_, Shape::Scalar) => left,
+                (Shape::Matrix(r1, c1), Shape::Matrix(r2, c2)) => {
+                    if c1 != r2 {
+                        self.error(operator, &format!("Cannot multiply {} by {}", left, right))
+                    } else {
+                        Shape::Matrix(*r1, *c2)
+                    }
+                }
+                _ => Shape::AnyMatrix,
+            },
+            TokenType::TOKEN_SLASH => match (&left, &right) {
+                (_, Shape::Scalar) => left,
+                _ => self.error(operator, &format!("Cannot divide {} by {}", left, right)),
+            },
+            TokenType::TOKEN_CARET => match (&left, &right) {
+                (Shape::Scalar, Shape::Scalar) => Shape::Scalar,
+                (Shape::Matrix(rows, cols), Shape::Scalar) if rows != cols => self.error(
+                    operator,
+                    &format!("Cannot raise a non-square {} matrix to a power", left),
+                ),
+                (_, Shape::Scalar) => left,
+                _ => self.error(
+                    operator,
+                    &format!("Cannot raise {} to the power of {}", left, right),
+                ),
+            },
+            _ => Shape::Unknown,
+        }
+    }
+
+    fn visit_logical(&mut self, logical: &Logical) -> Shape {
+        for side in [&logical.left, &logical.right] {
+            let shape = side.visit(self);
+            if !shape.maybe_bool() {
+                self.error(
+                    &logical.operator,
+                    &format!(
+                        "Operands of {} must be booleans, not {}",
+                        logical.operator.lexeme, shape
+                    ),
+                );
+            }
+        }
+        Shape::Bool
+    }
+
+    fn visit_call(&mut self, call: &Call) -> Shape {
+        let callee = call.callee.visit(self);
+        let arguments: Vec<Shape> = call
+            .arguments
+            .iter()
+            .map(|argument| argument.visit(self))
+            .collect();
+        // builtins like map call the functions they are given
+        let runs_function = |shape: &Shape| matches!(shape, Shape::Function(_) | Shape::Unknown);
+        if runs_function(&callee)
+            || matches!(callee, Shape::Builtin(_)) && arguments.iter().any(runs_function)
+        {
+            self.forget_assigned();
+        }
+        match callee {
+            Shape::Builtin(name) => {
+                let builtin = self.builtins.get(&name).unwrap();
+                let token = match call.callee.as_ref() {
+                    Expression::Identifier(identifier) => &identifier.name,
+                    _ => &call.paren,
+                };
+                let shape = builtin
+                    .check_arity(arguments.len())
+                    .and_then(|_| (builtin.shape)(&arguments, &call.arguments));
+                match shape {
+                    Ok(shape) => shape,
+                    Err(message) => self.error(token, &message),
+                }
+            }
+            Shape::Function(arity) if arity != call.arguments.len() => self.error(
+                &call.paren,
+                &format!(
+                    "Expected {} arguments but got {}.",
+                    arity,
+                    call.arguments.len()
+                ),
+            ),
+            // what a function returns depends on its arguments, so it is not tracked
+            Shape::Function(_) | Shape::Unknown => Shape::Unknown,
+            _ => self.error(&call.paren, "Can only call functions."),
+        }
+    }
+
+    fn visit_lambda(&mut self, lambda: &Lambda) -> Shape {
+        assigned_names(&lambda.body, &mut self.assigned_in_functions);
+        self.function_body(&lambda.params, &lambda.body);
+        Shape::Function(lambda.params.len())
+    }
+
+    fn visit_grouping(&mut self, grouping: &Grouping) -> Shape {
+        grouping.expression.visit(self)
+    }
+}
+
+impl StatementVisitor<()> for Analyzer<'_> {
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
+        statement.expression.visit(self);
+    }
+
+    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+        for expression in &statement.expressions {
+            expression.visit(self);
+        }
+        // a format string written out can be checked against its values now
+        if let Expression::Literal(literal) = &statement.expressions[0] {
+            if let ValueType::STRING(ref text) = literal.value.data {
+                let values = statement.expressions.len() - 1;
+                match format::parse(text) {
+                    Err(message) => {
+                        self.errors
+                            .push(Diagnostic::format(&statement.keyword, &message));
+                    }
+                    Ok(pieces) => {
+                        let placeholders = format::placeholders(&pieces);
+                        if placeholders > 0 && placeholders != values {
+                            self.errors.push(Diagnostic::format(
+                                &statement.keyword,
+                                &format!(
+                                    "The format string needs {} values but got {}",
+                                    placeholders, values
+                                ),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_let_statement(&mut self, statement: &LetStatement) {
+        let shape = statement.initializer.visit(self);
+        if self.warn_shadowing {
+            for name in &statement.names {
+                let lexeme = &name.lexeme;
+                let defined = self.scopes.iter().any(|scope| scope.contains_key(lexeme))
+                    || self.globals.lookup(lexeme).is_some();
+                if defined {
+                    self.warnings.push(Diagnostic::warning(
+                        name,
+                        &format!(
+                            "{} shadows an existing variable, use {} = ... to change it",
+                            lexeme, lexeme
+                        ),
+                    ));
+                }
+            }
+        }
+        if statement.names.len() == 1 {
+            self.define(&statement.names[0], shape);
+            return;
+        }
+        let shapes = match shape {
+            Shape::Tuple(shapes) if shapes.len() == statement.names.len() => shapes,
+            Shape::Unknown => vec![Shape::Unknown; statement.names.len()],
+            _ => {
+                self.error(
+                    &statement.names[0],
+                    &format!(
+                        "Cannot unpack {} into {} names",
+                        shape,
+                        statement.names.len()
+                    ),
+                );
+                // define the names anyway so later uses are not reported too
+                vec![Shape::Unknown; statement.names.len()]
+            }
+        };
+        for (name, shape) in statement.names.iter().zip(shapes) {
+            self.define(name, shape);
+        }
+    }
+
+    fn visit_assign_statement(&mut self, statement: &AssignStatement) {
+        let shape = statement.value.visit(self);
+        let name = &statement.name;
+        match self
+            .scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(&name.lexeme))
+        {
+            // a function can change a variable outside of it whenever it is
+            // called, so that variable could be anything afterwards
+            Some(i) if i < self.function_scope => {
+                self.scopes[i].insert(name.lexeme.to_string(), Shape::Unknown);
+            }
+            Some(i) => {
+                self.scopes[i].insert(name.lexeme.to_string(), shape);
+            }
+            None if self.globals.lookup(&name.lexeme).is_some() => {
+                let shape = if self.function_depth > 0 {
+                    Shape::Unknown
+                } else {
+                    shape
+                };
+                self.scopes[0].insert(name.lexeme.to_string(), shape);
+            }
+            None if self.function_depth > 0 => (),
+            None => {
+                self.error(name, "Undefined variable.");
+            }
+        }
+    }
+
+    fn visit_explain_statement(&mut self, statement: &ExplainStatement) {
+        statement.expression.visit(self);
+    }
+
+    fn visit_set_statement(&mut self, statement: &SetStatement) {
+        let shape = statement.value.visit(self);
+        if !shape.maybe_scalar() {
+            self.error(
+                &statement.name,
+                &format!("Setting {} must be a scalar", statement.name.lexeme),
+            );
+        }
+    }
+
+    fn visit_function_statement(&mut self, statement: &FunctionStatement) {
+        // defined first so the function can call itself
+        self.define(&statement.name, Shape::Function(statement.params.len()));
+        assigned_names(&statement.body, &mut self.assigned_in_functions);
+        self.function_body(&statement.params, &statement.body);
+    }
+
+    fn visit_return_statement(&mut self, statement: &ReturnStatement) {
+        statement.value.visit(self);
+    }
+
+    fn visit_if_statement(&mut self, statement: &IfStatement) {
+        self.condition(&statement.keyword, &statement.condition);
+        let then_shapes = self.branch(HashMap::new(), &statement.then_branch);
+        let else_shapes = self.branch(HashMap::new(), &statement.else_branch);
+        self.scopes = Analyzer::merge(then_shapes, else_shapes);
+    }
+
+    fn visit_while_statement(&mut self, statement: &WhileStatement) {
+        self.loop_body(HashMap::new(), &statement.body);
+        // checked after the body, against the shapes of every iteration
+        self.condition(&statement.keyword, &statement.condition);
+    }
+
+    fn visit_for_statement(&mut self, statement: &ForStatement) {
+        for bound in [&statement.start, &statement.end] {
+            let shape = bound.visit(self);
+            if !shape.maybe_scalar() {
+                self.error(
+                    &statement.range,
+                    &format!("Range bounds must be scalars, not {}", shape),
+                );
+            }
+        }
+        let mut scope = HashMap::new();
+        scope.insert(statement.variable.lexeme.to_string(), Shape::Scalar);
+        self.loop_body(scope, &statement.body);
+    }
+
+    fn visit_new_line_statement(&mut self, _statement: &NewLineStatement) {}
+}
+
+/// Adds every name `statements` assign to, including inside blocks and
+/// nested functions.
+fn assigned_names(statements: &[Statement], names: &mut HashSet<String>) {
+    for statement in statements {
+        match statement {
+            Statement::Assign(statement) => {
+                names.insert(statement.name.lexeme.to_string());
+            }
+            Statement::If(statement) => {
+                assigned_names(&statement.then_branch, names);
+                assigned_names(&statement.else_branch, names);
+            }
+            Statement::While(statement) => assigned_names(&statement.body, names),
+            Statement::For(statement) => assigned_names(&statement.body, names),
+            Statement::Function(statement) => assigned_names(&statement.body, names),
+            _ => (),
+        }
+    }
+}
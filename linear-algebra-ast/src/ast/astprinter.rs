@@ -1,51 +1,98 @@
 use crate::ast::expression::Binary;
+use crate::ast::expression::Expression;
 use crate::ast::expression::ExpressionVisitor;
 use crate::ast::expression::ExpressionType;
 use crate::ast::expression::Literal;
 use crate::ast::expression::Unary;
+use crate::settings::Settings;
 use crate::value::Value;
 
-pub struct ASTPrinter {}
+/// Renders expressions in prefix form, e.g. `(+ 1 (* 2 3))`, for debugging
+/// the parser. The text is returned rather than printed so the caller picks
+/// where it goes.
+pub struct ASTPrinter {
+    text: String,
+}
+impl Default for ASTPrinter {
+    fn default() -> Self {
+        ASTPrinter::new()
+    }
+}
+
 impl ASTPrinter {
     pub fn new() -> Self {
-        ASTPrinter {}
+        ASTPrinter {
+            text: String::new(),
+        }
+    }
+
+    pub fn print(&mut self, expression: &Expression) -> String {
+        self.text.clear();
+        expression.visit(self);
+        std::mem::take(&mut self.text)
     }
-    // pub fn print(&mut self, expression: &dyn Expression<Value>) -> Value {
-    //     expression.accept(self)
-    // }
 }
 
 impl ExpressionVisitor<Value> for ASTPrinter {
-    fn visit_identifier(&mut self, _identifier: &super::expression::Identifier) -> Value {
+    fn visit_identifier(&mut self, identifier: &super::expression::Identifier) -> Value {
+        self.text.push_str(&identifier.name.lexeme);
         Value::new_scalar(0.0)
     }
 
     fn visit_literal(&mut self, literal: &Literal) -> Value {
         let value: &Value = &literal.value;
-        value.print();
+        self.text.push_str(&value.to_text(&Settings::new()));
         value.to_owned()
     }
 
     fn visit_unary(&mut self, unary: &Unary) -> Value {
-        print!("({} ", unary.operator.lexeme);
+        self.text.push_str(&format!("({} ", unary.operator.lexeme));
         let value = unary.right.visit(self);
-        print!(")");
+        self.text.push(')');
         value
     }
 
     fn visit_binary(&mut self, binary: &Binary) -> Value {
-        print!("(");
+        self.text.push('(');
         let left = binary.left.visit(self);
-        print!(" {} ", binary.operator.lexeme);
+        self.text.push_str(&format!(" {} ", binary.operator.lexeme));
         let _right = binary.right.visit(self);
-        print!(")");
+        self.text.push(')');
         left
     }
 
+    fn visit_logical(&mut self, logical: &super::expression::Logical) -> Value {
+        self.text.push('(');
+        let left = logical.left.visit(self);
+        self.text
+            .push_str(&format!(" {} ", logical.operator.lexeme));
+        logical.right.visit(self);
+        self.text.push(')');
+        left
+    }
+
+    fn visit_call(&mut self, call: &super::expression::Call) -> Value {
+        self.text.push_str("(call ");
+        let value = call.callee.visit(self);
+        for argument in &call.arguments {
+            self.text.push(' ');
+            argument.visit(self);
+        }
+        self.text.push(')');
+        value
+    }
+
+    fn visit_lambda(&mut self, lambda: &super::expression::Lambda) -> Value {
+        let params: Vec<&str> = lambda.params.iter().map(|p| p.lexeme.as_str()).collect();
+        self.text
+            .push_str(&format!("(lambda ({}))", params.join(" ")));
+        Value::new_scalar(0.0)
+    }
+
     fn visit_grouping(&mut self, grouping: &super::expression::Grouping) -> Value {
-        print!("(");
+        self.text.push('(');
         let value = grouping.expression.visit(self);
-        print!(")");
+        self.text.push(')');
         value
     }
 }
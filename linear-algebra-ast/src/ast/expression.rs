@@ -1,3 +1,6 @@
+use std::rc::Rc;
+
+use crate::ast::statement::Statement;
 use crate::tokens::Token;
 use crate::value::Value;
 
@@ -5,8 +8,11 @@ pub trait ExpressionVisitor<T> {
     fn visit_literal(&mut self, literal: &Literal) -> T;
     fn visit_unary(&mut self, unary: &Unary) -> T;
     fn visit_binary(&mut self, binary: &Binary) -> T;
+    fn visit_logical(&mut self, logical: &Logical) -> T;
     fn visit_grouping(&mut self, grouping: &Grouping) -> T;
     fn visit_identifier(&mut self, identifier: &Identifier) -> T;
+    fn visit_call(&mut self, call: &Call) -> T;
+    fn visit_lambda(&mut self, lambda: &Lambda) -> T;
 }
 
 pub trait ExpressionType<T> {
@@ -20,8 +26,8 @@ impl Literal {
         Literal { value }
     }
 }
-impl ExpressionType<Value> for Literal {
-    fn visit(&self, visitor: &mut dyn ExpressionVisitor<Value>) -> Value {
+impl<T> ExpressionType<T> for Literal {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
         visitor.visit_literal(self)
     }
 }
@@ -35,8 +41,8 @@ impl Unary {
         Unary { operator, right }
     }
 }
-impl ExpressionType<Value> for Unary {
-    fn visit(&self, visitor: &mut dyn ExpressionVisitor<Value>) -> Value {
+impl<T> ExpressionType<T> for Unary {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
         visitor.visit_unary(self)
     }
 }
@@ -59,12 +65,34 @@ impl Binary {
         }
     }
 }
-impl ExpressionType<Value> for Binary {
-    fn visit(&self, visitor: &mut dyn ExpressionVisitor<Value>) -> Value {
+impl<T> ExpressionType<T> for Binary {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
         visitor.visit_binary(self)
     }
 }
 
+/// `and` and `or`, kept apart from Binary because the right side is only
+/// evaluated when it decides the result.
+pub struct Logical {
+    pub left: Box<Expression>,
+    pub operator: Token,
+    pub right: Box<Expression>,
+}
+impl Logical {
+    pub fn new(left: Box<Expression>, operator: Token, right: Box<Expression>) -> Self {
+        Logical {
+            left,
+            operator,
+            right,
+        }
+    }
+}
+impl<T> ExpressionType<T> for Logical {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
+        visitor.visit_logical(self)
+    }
+}
+
 pub struct Grouping {
     pub expression: Box<Expression>,
 }
@@ -73,33 +101,79 @@ impl Grouping {
         Grouping { expression }
     }
 }
-impl ExpressionType<Value> for Grouping {
-    fn visit(&self, visitor: &mut dyn ExpressionVisitor<Value>) -> Value {
+impl<T> ExpressionType<T> for Grouping {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
         self.expression.visit(visitor)
     }
 }
 
 
 pub struct Identifier {
-    pub name: String,
+    pub name: Token,
 }
 impl Identifier {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: Token) -> Self {
         Identifier { name }
     }
 }
-impl ExpressionType<Value> for Identifier {
-    fn visit(&self, visitor: &mut dyn ExpressionVisitor<Value>) -> Value {
+impl<T> ExpressionType<T> for Identifier {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
         visitor.visit_identifier(self)
     }
 }
 
+pub struct Call {
+    pub callee: Box<Expression>,
+    /// the closing paren, where errors about the call are reported
+    pub paren: Token,
+    pub arguments: Vec<Expression>,
+}
+impl Call {
+    pub fn new(callee: Box<Expression>, paren: Token, arguments: Vec<Expression>) -> Self {
+        Call {
+            callee,
+            paren,
+            arguments,
+        }
+    }
+}
+impl<T> ExpressionType<T> for Call {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
+        visitor.visit_call(self)
+    }
+}
+
+/// An anonymous function, `x -> expr` or `(x, y) -> expr`.
+pub struct Lambda {
+    pub arrow: Token,
+    pub params: Vec<Token>,
+    /// a single return of the expression, so lambdas run like any other function
+    pub body: Rc<Vec<Statement>>,
+}
+impl Lambda {
+    pub fn new(arrow: Token, params: Vec<Token>, body: Vec<Statement>) -> Self {
+        Lambda {
+            arrow,
+            params,
+            body: Rc::new(body),
+        }
+    }
+}
+impl<T> ExpressionType<T> for Lambda {
+    fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
+        visitor.visit_lambda(self)
+    }
+}
+
 pub enum Expression {
     Literal(Literal),
     Unary(Unary),
     Binary(Binary),
+    Logical(Logical),
     Grouping(Grouping),
     Identifier(Identifier),
+    Call(Call),
+    Lambda(Lambda),
 }
 impl<T> ExpressionType<T> for Expression {
     fn visit(&self, visitor: &mut dyn ExpressionVisitor<T>) -> T {
@@ -107,8 +181,11 @@ impl<T> ExpressionType<T> for Expression {
             Expression::Literal(literal) => visitor.visit_literal(literal),
             Expression::Unary(unary) => visitor.visit_unary(unary),
             Expression::Binary(binary) => visitor.visit_binary(binary),
+            Expression::Logical(logical) => visitor.visit_logical(logical),
             Expression::Grouping(grouping) => visitor.visit_grouping(grouping),
             Expression::Identifier(identifier) => visitor.visit_identifier(identifier),
+            Expression::Call(call) => visitor.visit_call(call),
+            Expression::Lambda(lambda) => visitor.visit_lambda(lambda),
         }
     }
 }
@@ -1,90 +1,296 @@
+use std::rc::Rc;
 
 use crate::tokens::Token;
-use crate::value::Value;
-use crate::ast::expression::ExpressionType;
+use crate::ast::expression::Expression;
 
-pub trait StatementVisitor {
-    fn visit_expression_statement(&mut self, statement: &ExpressionStatement);
-    fn visit_print_statement(&mut self, statement: &PrintStatement);
-    fn visit_let_statement(&mut self, statement: &LetStatement);
-    fn visit_new_line_statement(&mut self, statement: &NewLineStatement);
+pub trait StatementVisitor<T> {
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) -> T;
+    fn visit_print_statement(&mut self, statement: &PrintStatement) -> T;
+    fn visit_let_statement(&mut self, statement: &LetStatement) -> T;
+    fn visit_assign_statement(&mut self, statement: &AssignStatement) -> T;
+    fn visit_new_line_statement(&mut self, statement: &NewLineStatement) -> T;
+    fn visit_explain_statement(&mut self, statement: &ExplainStatement) -> T;
+    fn visit_set_statement(&mut self, statement: &SetStatement) -> T;
+    fn visit_function_statement(&mut self, statement: &FunctionStatement) -> T;
+    fn visit_return_statement(&mut self, statement: &ReturnStatement) -> T;
+    fn visit_if_statement(&mut self, statement: &IfStatement) -> T;
+    fn visit_while_statement(&mut self, statement: &WhileStatement) -> T;
+    fn visit_for_statement(&mut self, statement: &ForStatement) -> T;
 }
 
-pub trait StatementType {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> ();
+pub trait StatementType<T> {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T;
 }
 
 pub struct ExpressionStatement {
-    pub expression: Box<dyn ExpressionType<Value>>,
+    pub expression: Box<Expression>,
 }
 impl ExpressionStatement {
-    pub fn new(expression: Box<dyn ExpressionType<Value>>) -> Self {
+    pub fn new(expression: Box<Expression>) -> Self {
         ExpressionStatement { expression }
     }
 }
-impl StatementType for ExpressionStatement {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> () {
+impl<T> StatementType<T> for ExpressionStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
         visitor.visit_expression_statement(self)
     }
 }
 
+/// `print a, b;` or `print "a = {}, b = {}", a, b;`
 pub struct PrintStatement {
-    pub expression: Box<dyn ExpressionType<Value>>,
+    pub keyword: Token,
+    pub expressions: Vec<Expression>,
 }
 impl PrintStatement {
-    pub fn new(expression: Box<dyn ExpressionType<Value>>) -> Self {
-        PrintStatement { expression }
+    pub fn new(keyword: Token, expressions: Vec<Expression>) -> Self {
+        PrintStatement {
+            keyword,
+            expressions,
+        }
     }
 }
-impl StatementType for PrintStatement {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> () {
+impl<T> StatementType<T> for PrintStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
         visitor.visit_print_statement(self)
     }
 }
 
 pub struct LetStatement {
-    pub name: Token,
-    pub initializer: Box<dyn ExpressionType<Value>>,
+    pub names: Vec<Token>,
+    pub initializer: Box<Expression>,
+    /// made with const, so the names cannot be assigned or redefined
+    pub constant: bool,
+    /// the /// comments written above it
+    pub doc: Option<String>,
 }
 impl LetStatement {
-    pub fn new(name: Token, initializer: Box<dyn ExpressionType<Value>>) -> Self {
-        LetStatement { name, initializer }
+    pub fn new(names: Vec<Token>, initializer: Box<Expression>, constant: bool) -> Self {
+        LetStatement {
+            names,
+            initializer,
+            constant,
+            doc: None,
+        }
     }
 }
-impl StatementType for LetStatement {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> () {
+impl<T> StatementType<T> for LetStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
         visitor.visit_let_statement(self)
     }
 }
 
+/// `x = value;`, compound forms like `x += value;` are parsed into this too.
+pub struct AssignStatement {
+    pub name: Token,
+    pub value: Box<Expression>,
+}
+impl AssignStatement {
+    pub fn new(name: Token, value: Box<Expression>) -> Self {
+        AssignStatement { name, value }
+    }
+}
+impl<T> StatementType<T> for AssignStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_assign_statement(self)
+    }
+}
+
 pub struct NewLineStatement {
+    pub keyword: Token,
     pub lines: usize,
 }
 impl NewLineStatement {
-    pub fn new(lines: usize) -> Self {
-        NewLineStatement { lines }
+    pub fn new(keyword: Token, lines: usize) -> Self {
+        NewLineStatement { keyword, lines }
     }
 }
-impl StatementType for NewLineStatement {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> () {
+impl<T> StatementType<T> for NewLineStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
         visitor.visit_new_line_statement(self)
     }
 }
 
+pub struct ExplainStatement {
+    pub keyword: Token,
+    pub expression: Box<Expression>,
+}
+impl ExplainStatement {
+    pub fn new(keyword: Token, expression: Box<Expression>) -> Self {
+        ExplainStatement {
+            keyword,
+            expression,
+        }
+    }
+}
+impl<T> StatementType<T> for ExplainStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_explain_statement(self)
+    }
+}
+
+pub struct SetStatement {
+    pub name: Token,
+    pub value: Box<Expression>,
+}
+impl SetStatement {
+    pub fn new(name: Token, value: Box<Expression>) -> Self {
+        SetStatement { name, value }
+    }
+}
+impl<T> StatementType<T> for SetStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_set_statement(self)
+    }
+}
+
+pub struct FunctionStatement {
+    pub name: Token,
+    pub params: Vec<Token>,
+    /// shared with every function value made from this declaration
+    pub body: Rc<Vec<Statement>>,
+    /// the /// comments written above it
+    pub doc: Option<String>,
+}
+impl FunctionStatement {
+    pub fn new(name: Token, params: Vec<Token>, body: Vec<Statement>) -> Self {
+        FunctionStatement {
+            name,
+            params,
+            body: Rc::new(body),
+            doc: None,
+        }
+    }
+}
+impl<T> StatementType<T> for FunctionStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_function_statement(self)
+    }
+}
+
+pub struct ReturnStatement {
+    pub value: Box<Expression>,
+}
+impl ReturnStatement {
+    pub fn new(value: Box<Expression>) -> Self {
+        ReturnStatement { value }
+    }
+}
+impl<T> StatementType<T> for ReturnStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_return_statement(self)
+    }
+}
+
+pub struct IfStatement {
+    pub keyword: Token,
+    pub condition: Box<Expression>,
+    pub then_branch: Vec<Statement>,
+    /// empty when there is no else, `else if` is an else holding another if
+    pub else_branch: Vec<Statement>,
+}
+impl IfStatement {
+    pub fn new(
+        keyword: Token,
+        condition: Box<Expression>,
+        then_branch: Vec<Statement>,
+        else_branch: Vec<Statement>,
+    ) -> Self {
+        IfStatement {
+            keyword,
+            condition,
+            then_branch,
+            else_branch,
+        }
+    }
+}
+impl<T> StatementType<T> for IfStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_if_statement(self)
+    }
+}
+
+pub struct WhileStatement {
+    pub keyword: Token,
+    pub condition: Box<Expression>,
+    pub body: Vec<Statement>,
+}
+impl WhileStatement {
+    pub fn new(keyword: Token, condition: Box<Expression>, body: Vec<Statement>) -> Self {
+        WhileStatement {
+            keyword,
+            condition,
+            body,
+        }
+    }
+}
+impl<T> StatementType<T> for WhileStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_while_statement(self)
+    }
+}
+
+/// `for i in start..end { ... }`, counting up by one with both ends included.
+pub struct ForStatement {
+    pub variable: Token,
+    /// the `..` token, where bad bounds are reported
+    pub range: Token,
+    pub start: Box<Expression>,
+    pub end: Box<Expression>,
+    pub body: Vec<Statement>,
+}
+impl ForStatement {
+    pub fn new(
+        variable: Token,
+        range: Token,
+        start: Box<Expression>,
+        end: Box<Expression>,
+        body: Vec<Statement>,
+    ) -> Self {
+        ForStatement {
+            variable,
+            range,
+            start,
+            end,
+            body,
+        }
+    }
+}
+impl<T> StatementType<T> for ForStatement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
+        visitor.visit_for_statement(self)
+    }
+}
+
 pub enum Statement {
     Expression(ExpressionStatement),
     Print(PrintStatement),
     Let(LetStatement),
-    NewLine(NewLineStatement)
+    Assign(AssignStatement),
+    NewLine(NewLineStatement),
+    Explain(ExplainStatement),
+    Set(SetStatement),
+    Function(FunctionStatement),
+    Return(ReturnStatement),
+    If(IfStatement),
+    While(WhileStatement),
+    For(ForStatement),
 }
 
-impl StatementType for Statement {
-    fn visit(&self, visitor: &mut dyn StatementVisitor) -> () {
+impl<T> StatementType<T> for Statement {
+    fn visit(&self, visitor: &mut dyn StatementVisitor<T>) -> T {
         match self {
             Statement::Expression(statement) => statement.visit(visitor),
             Statement::Print(statement) => statement.visit(visitor),
             Statement::Let(statement) => statement.visit(visitor),
-            Statement::NewLine(statement) => statement.visit(visitor)
+            Statement::Assign(statement) => statement.visit(visitor),
+            Statement::NewLine(statement) => statement.visit(visitor),
+            Statement::Explain(statement) => statement.visit(visitor),
+            Statement::Set(statement) => statement.visit(visitor),
+            Statement::Function(statement) => statement.visit(visitor),
+            Statement::Return(statement) => statement.visit(visitor),
+            Statement::If(statement) => statement.visit(visitor),
+            Statement::While(statement) => statement.visit(visitor),
+            Statement::For(statement) => statement.visit(visitor),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,716 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use crate::analyzer::Shape;
+use crate::ast::expression::Expression;
+use crate::error::Diagnostic;
+use crate::interpreter::Interpreter;
+use crate::polynomial::Polynomial;
+use crate::tokens::Token;
+use crate::value::Matrix;
+use crate::value::RowOperation;
+use crate::value::Value;
+use crate::value::ValueType;
+
+/// Runs a builtin on its evaluated arguments, reporting errors at `token`.
+pub type BuiltinFn = dyn Fn(&mut Interpreter, &Token, Vec<Value>) -> Result<Value, Diagnostic>;
+
+/// What a builtin returns given the shapes of its arguments, or why it cannot
+/// take them. The expressions are there for arguments whose value matters
+/// before the program runs, like the size given to identity.
+pub type ShapeFn = fn(&[Shape], &[Expression]) -> Result<Shape, String>;
+
+/// A function scripts can call without defining it, like `inv(A)`.
+pub struct Builtin {
+    pub name: String,
+    /// how many arguments it takes
+    pub arity: RangeInclusive<usize>,
+    /// how to call it, shown when the number of arguments is wrong
+    pub usage: String,
+    pub shape: ShapeFn,
+    pub call: Box<BuiltinFn>,
+}
+
+impl Builtin {
+    pub fn check_arity(&self, count: usize) -> Result<(), String> {
+        if self.arity.contains(&count) {
+            Ok(())
+        } else {
+            Err(format!("Expected a call of the form {}", self.usage))
+        }
+    }
+}
+
+/// The builtins by name. Scripts call them like any other function, so a new
+/// one only has to be registered here.
+#[derive(Default)]
+pub struct BuiltinRegistry {
+    builtins: HashMap<String, Rc<Builtin>>,
+}
+
+impl BuiltinRegistry {
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: RangeInclusive<usize>,
+        usage: &str,
+        shape: ShapeFn,
+        call: impl Fn(&mut Interpreter, &Token, Vec<Value>) -> Result<Value, Diagnostic> + 'static,
+    ) {
+        self.builtins.insert(
+            name.to_string(),
+            Rc::new(Builtin {
+                name: name.to_string(),
+                arity,
+                usage: usage.to_string(),
+                shape,
+                call: Box::new(call),
+            }),
+        );
+    }
+
+    /// Makes `alias` another name for the builtin `name`.
+    pub fn alias(&mut self, alias: &str, name: &str) {
+        let builtin = Rc::clone(&self.builtins[name]);
+        self.builtins.insert(alias.to_string(), builtin);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rc<Builtin>> {
+        self.builtins.get(name).cloned()
+    }
+
+    /// Every builtin the language comes with.
+    pub fn standard() -> Self {
+        let mut registry = BuiltinRegistry::default();
+        registry.register(
+            "identity",
+            1..=1,
+            "identity(n)",
+            identity_shape,
+            |_, token, args| Ok(Value::wrap_matrix(identity(token, &args[0])?)),
+        );
+        registry.alias("iden", "identity");
+
+        registry.register(
+            "ref",
+            1..=1,
+            "ref(A)",
+            reduced_shape,
+            |interpreter, token, args| {
+                let mut matrix = matrix(token, &args[0])?;
+                interpreter.row_reduce(token, &mut matrix, Matrix::ref_matrix_recorded)?;
+                Ok(Value::wrap_matrix(matrix))
+            },
+        );
+        registry.register(
+            "rref",
+            1..=1,
+            "rref(A)",
+            reduced_shape,
+            |interpreter, token, args| {
+                let mut matrix = matrix(token, &args[0])?;
+                interpreter.row_reduce(token, &mut matrix, Matrix::rref_matrix_recorded)?;
+                Ok(Value::wrap_matrix(matrix))
+            },
+        );
+        registry.register(
+            "inv",
+            1..=1,
+            "inv(A)",
+            inverse_shape,
+            |interpreter, token, args| {
+                let mut matrix = matrix(token, &args[0])?;
+                if interpreter.row_reduce(token, &mut matrix, Matrix::inverse_recorded)? {
+                    Ok(Value::wrap_matrix(matrix))
+                } else {
+                    Err(Diagnostic::runtime(token, "Matrix is not invertible"))
+                }
+            },
+        );
+        registry.alias("inverse", "inv");
+
+        registry.register(
+            "roots",
+            1..=1,
+            "roots(p)",
+            roots_shape,
+            |interpreter, token, args| {
+                let coefficients = matrix(token, &args[0])?;
+                if coefficients.rows != 1 && coefficients.cols != 1 {
+                    return Err(Diagnostic::runtime(
+                        token,
+                        "Polynomial coefficients must be a row or column vector",
+                    ));
+                }
+                let polynomial = Polynomial::new(coefficients.data);
+                if polynomial.degree() < 1 {
+                    return Err(Diagnostic::runtime(
+                        token,
+                        "Polynomial must have degree at least 1 to have roots",
+                    ));
+                }
+                // one row per root, real part then imaginary part
+                let tolerance = interpreter.settings.tolerance;
+                let roots = polynomial.roots();
+                let mut data = Vec::new();
+                for root in &roots {
+                    for part in [root.re, root.im] {
+                        data.push(if part.abs() < tolerance { 0.0 } else { part });
+                    }
+                }
+                Ok(Value::new_matrix(data, roots.len(), 2))
+            },
+        );
+        registry.register(
+            "charpoly",
+            1..=1,
+            "charpoly(A)",
+            charpoly_shape,
+            |_, token, args| {
+                let matrix = square(token, &args[0])?;
+                let polynomial = matrix.characteristic_polynomial();
+                let degree = polynomial.coefficients.len();
+                Ok(Value::new_matrix(polynomial.coefficients, 1, degree))
+            },
+        );
+        registry.register(
+            "diagonalize",
+            1..=1,
+            "diagonalize(A)",
+            diagonalize_shape,
+            |interpreter, token, args| {
+                let matrix = square(token, &args[0])?;
+                match matrix.diagonalize(interpreter.settings.tolerance) {
+                    Ok((p, d)) => Ok(Value::wrap_tuple(vec![
+                        Value::wrap_matrix(p),
+                        Value::wrap_matrix(d),
+                    ])),
+                    Err(message) => Err(Diagnostic::runtime(token, &message)),
+                }
+            },
+        );
+        registry.register(
+            "jordan",
+            1..=1,
+            "jordan(A)",
+            jordan_shape,
+            |interpreter, token, args| {
+                let matrix = square(token, &args[0])?;
+                match matrix.jordan(interpreter.settings.tolerance) {
+                    Ok(j) => Ok(Value::wrap_matrix(j)),
+                    Err(message) => Err(Diagnostic::runtime(token, &message)),
+                }
+            },
+        );
+
+        registry.register(
+            "swap",
+            3..=3,
+            "swap(A, i, j)",
+            row_operation_shape,
+            |interpreter, token, args| {
+                let matrix = row_operand(token, &args[0])?;
+                let operation = RowOperation::Swap(
+                    row_index(token, &args[1], matrix.rows)?,
+                    row_index(token, &args[2], matrix.rows)?,
+                );
+                apply(interpreter, token, matrix, operation)
+            },
+        );
+        registry.register(
+            "scale",
+            3..=3,
+            "scale(A, i, k)",
+            row_operation_shape,
+            |interpreter, token, args| {
+                let matrix = row_operand(token, &args[0])?;
+                let row = row_index(token, &args[1], matrix.rows)?;
+                let operation = match args[2].data {
+                    ValueType::SCALAR(ref s) if s.data != 0.0 => RowOperation::Scale(row, s.data),
+                    _ => {
+                        return Err(Diagnostic::runtime(
+                            token,
+                            "Rows can only be scaled by a non-zero scalar.",
+                        ))
+                    }
+                };
+                apply(interpreter, token, matrix, operation)
+            },
+        );
+        registry.register(
+            "addrow",
+            4..=4,
+            "addrow(A, target, source, k)",
+            row_operation_shape,
+            |interpreter, token, args| {
+                let matrix = row_operand(token, &args[0])?;
+                let row = row_index(token, &args[1], matrix.rows)?;
+                let source = row_index(token, &args[2], matrix.rows)?;
+                if source == row {
+                    return Err(Diagnostic::runtime(
+                        token,
+                        "Cannot add a multiple of a row to itself.",
+                    ));
+                }
+                let operation = match args[3].data {
+                    ValueType::SCALAR(ref s) => RowOperation::Add(row, source, s.data),
+                    _ => return Err(Diagnostic::runtime(token, "Row multiple must be a scalar.")),
+                };
+                apply(interpreter, token, matrix, operation)
+            },
+        );
+        // elem(n, op, ...) is op(identity(n), ...)
+        registry.register(
+            "elem",
+            4..=5,
+            "elem(n, swap, i, j), elem(n, scale, i, k) or elem(n, addrow, target, source, k)",
+            elem_shape,
+            |interpreter, token, mut args| {
+                let size = args.remove(0);
+                let operation = args.remove(0);
+                if !matches!(&operation.data, ValueType::BUILTIN(b) if is_row_operation(&b.name)) {
+                    return Err(Diagnostic::runtime(
+                        token,
+                        "Expect swap, scale or addrow as the operation of elem",
+                    ));
+                }
+                args.insert(0, Value::wrap_matrix(identity(token, &size)?));
+                interpreter.call_value(&operation, args, token)
+            },
+        );
+
+        registry.register(
+            "map",
+            2..=2,
+            "map(A, f)",
+            map_shape,
+            |interpreter, token, args| {
+                let (matrix, function) = higher_order_arguments(token, &args)?;
+                let mut data = Vec::new();
+                for entry in &matrix.data {
+                    let result = interpreter.call_value(
+                        &function,
+                        vec![Value::new_scalar(*entry)],
+                        token,
+                    )?;
+                    match result.data {
+                        ValueType::SCALAR(s) => data.push(s.data),
+                        _ => {
+                            return Err(Diagnostic::runtime(
+                                token,
+                                "The function given to map must return a scalar.",
+                            ))
+                        }
+                    }
+                }
+                Ok(Value::new_matrix(data, matrix.rows, matrix.cols))
+            },
+        );
+        registry.register(
+            "reduce",
+            2..=3,
+            "reduce(A, f) or reduce(A, f, initial)",
+            reduce_shape,
+            |interpreter, token, args| {
+                let (matrix, function) = higher_order_arguments(token, &args)?;
+                // entries are folded left to right, top to bottom
+                let mut entries = matrix.data.iter().map(|entry| Value::new_scalar(*entry));
+                let mut accumulator = match args.get(2) {
+                    Some(initial) => initial.clone(),
+                    None => entries.next().ok_or_else(|| {
+                        Diagnostic::runtime(
+                            token,
+                            "reduce needs an initial value for an empty matrix",
+                        )
+                    })?,
+                };
+                for entry in entries {
+                    accumulator =
+                        interpreter.call_value(&function, vec![accumulator, entry], token)?;
+                }
+                Ok(accumulator)
+            },
+        );
+        registry.register(
+            "rowmap",
+            2..=2,
+            "rowmap(A, f)",
+            rowmap_shape,
+            |interpreter, token, args| map_parts(interpreter, token, args, true),
+        );
+        registry.register(
+            "colmap",
+            2..=2,
+            "colmap(A, f)",
+            colmap_shape,
+            |interpreter, token, args| map_parts(interpreter, token, args, false),
+        );
+        registry
+    }
+}
+
+fn matrix(token: &Token, value: &Value) -> Result<Matrix, Diagnostic> {
+    match &value.data {
+        ValueType::MATRIX(m) => Ok(m.clone()),
+        _ => Err(Diagnostic::runtime(
+            token,
+            &format!("Expected a matrix, not {}.", Shape::of(value)),
+        )),
+    }
+}
+
+fn square(token: &Token, value: &Value) -> Result<Matrix, Diagnostic> {
+    let matrix = matrix(token, value)?;
+    if matrix.rows != matrix.cols {
+        return Err(Diagnostic::runtime(
+            token,
+            &format!("Expected a square matrix, not {}.", Shape::of(value)),
+        ));
+    }
+    Ok(matrix)
+}
+
+/// The largest identity a script can ask for, anything bigger is far more
+/// likely a mistake than a matrix that fits in memory.
+const MAX_IDENTITY_SIZE: usize = 1000;
+
+fn identity(token: &Token, size: &Value) -> Result<Matrix, Diagnostic> {
+    match size.data {
+        ValueType::SCALAR(ref s) => match identity_size(s.data) {
+            Some(n) => Ok(Matrix::new_identity(n)),
+            None => Err(Diagnostic::runtime(token, &identity_message())),
+        },
+        _ => Err(Diagnostic::runtime(token, &identity_message())),
+    }
+}
+
+/// The size of an identity matrix, if `n` is a whole number in range.
+fn identity_size(n: f64) -> Option<usize> {
+    if n >= 1.0 && n <= MAX_IDENTITY_SIZE as f64 && n.fract() == 0.0 {
+        Some(n as usize)
+    } else {
+        None
+    }
+}
+
+fn identity_message() -> String {
+    format!(
+        "Identity should be of size (n) where n is a whole number from 1 to {}.",
+        MAX_IDENTITY_SIZE
+    )
+}
+
+fn is_row_operation(name: &str) -> bool {
+    matches!(name, "swap" | "scale" | "addrow")
+}
+
+fn row_operand(token: &Token, value: &Value) -> Result<Matrix, Diagnostic> {
+    match &value.data {
+        ValueType::MATRIX(m) => Ok(m.clone()),
+        _ => Err(Diagnostic::runtime(
+            token,
+            "Row operations can only be applied to a matrix.",
+        )),
+    }
+}
+
+/// Converts a one-based row number from a script into a row index.
+fn row_index(token: &Token, value: &Value, rows: usize) -> Result<usize, Diagnostic> {
+    match value.data {
+        ValueType::SCALAR(ref s)
+            if s.data >= 1.0 && s.data <= rows as f64 && s.data.fract() == 0.0 =>
+        {
+            Ok(s.data as usize - 1)
+        }
+        _ => Err(Diagnostic::runtime(
+            token,
+            &format!("Row must be a whole number from 1 to {}.", rows),
+        )),
+    }
+}
+
+fn apply(
+    interpreter: &mut Interpreter,
+    token: &Token,
+    mut matrix: Matrix,
+    operation: RowOperation,
+) -> Result<Value, Diagnostic> {
+    matrix.apply(&operation);
+    if interpreter.explain {
+        interpreter.print_step(token, operation, &matrix)?;
+    }
+    Ok(Value::wrap_matrix(matrix))
+}
+
+/// The matrix and function given to map, reduce, rowmap or colmap.
+fn higher_order_arguments(token: &Token, args: &[Value]) -> Result<(Matrix, Value), Diagnostic> {
+    let matrix = match &args[0].data {
+        ValueType::MATRIX(m) => m.clone(),
+        _ => {
+            return Err(Diagnostic::runtime(
+                token,
+                &format!(
+                    "Expected a matrix as the first argument of {}.",
+                    token.lexeme
+                ),
+            ))
+        }
+    };
+    match &args[1].data {
+        ValueType::FUNCTION(_) | ValueType::BUILTIN(_) => Ok((matrix, args[1].clone())),
+        _ => Err(Diagnostic::runtime(
+            token,
+            &format!(
+                "Expected a function as the second argument of {}.",
+                token.lexeme
+            ),
+        )),
+    }
+}
+
+/// rowmap and colmap, each result becomes a row (or column) of the output.
+fn map_parts(
+    interpreter: &mut Interpreter,
+    token: &Token,
+    args: Vec<Value>,
+    by_rows: bool,
+) -> Result<Value, Diagnostic> {
+    let (matrix, function) = higher_order_arguments(token, &args)?;
+    if matrix.data.is_empty() {
+        return Err(Diagnostic::runtime(
+            token,
+            &format!("{} needs a matrix with at least one entry", token.lexeme),
+        ));
+    }
+    let (count, length) = if by_rows {
+        (matrix.rows, matrix.cols)
+    } else {
+        (matrix.cols, matrix.rows)
+    };
+    let mut results: Vec<Vec<f64>> = Vec::new();
+    for i in 0..count {
+        let part: Vec<f64> = (0..length)
+            .map(|j| {
+                if by_rows {
+                    matrix.data[i * matrix.cols + j]
+                } else {
+                    matrix.data[j * matrix.cols + i]
+                }
+            })
+            .collect();
+        let part = if by_rows {
+            Value::new_matrix(part, 1, length)
+        } else {
+            Value::new_matrix(part, length, 1)
+        };
+        let result = interpreter.call_value(&function, vec![part], token)?;
+        let values = match result.data {
+            ValueType::SCALAR(s) => vec![s.data],
+            ValueType::MATRIX(m) if (by_rows && m.rows == 1) || (!by_rows && m.cols == 1) => m.data,
+            _ => {
+                return Err(Diagnostic::runtime(
+                    token,
+                    &format!(
+                        "The function given to {} must return a scalar or a {} vector.",
+                        token.lexeme,
+                        if by_rows { "row" } else { "column" }
+                    ),
+                ))
+            }
+        };
+        if !results.is_empty() && results[0].len() != values.len() {
+            return Err(Diagnostic::runtime(
+                token,
+                &format!(
+                    "The function given to {} must return vectors of the same length.",
+                    token.lexeme
+                ),
+            ));
+        }
+        results.push(values);
+    }
+    let width = results[0].len();
+    if by_rows {
+        Ok(Value::new_matrix(results.concat(), count, width))
+    } else {
+        let mut data = vec![0.0; width * count];
+        for (j, column) in results.iter().enumerate() {
+            for (i, entry) in column.iter().enumerate() {
+                data[i * count + j] = *entry;
+            }
+        }
+        Ok(Value::new_matrix(data, width, count))
+    }
+}
+
+/// Builtins that always return a matrix keep a known size and otherwise
+/// return some matrix.
+fn some_matrix(shape: &Shape) -> Shape {
+    match shape {
+        Shape::Matrix(_, _) => shape.clone(),
+        _ => Shape::AnyMatrix,
+    }
+}
+
+fn matrix_shape(shape: &Shape) -> Result<Shape, String> {
+    if shape.maybe_matrix() {
+        Ok(some_matrix(shape))
+    } else {
+        Err(format!("Expected a matrix, not {}", shape))
+    }
+}
+
+/// Shared checks for functions that only work on square matrices.
+fn square_shape(shape: &Shape, action: &str) -> Result<Shape, String> {
+    match shape {
+        Shape::Scalar => Err(format!("Cannot {} a scalar", action)),
+        Shape::Matrix(rows, cols) if rows != cols => {
+            Err(format!("Cannot {} a non-square {} matrix", action, shape))
+        }
+        _ => matrix_shape(shape),
+    }
+}
+
+/// The size given to identity can be checked when it is written out.
+fn identity_shape(args: &[Shape], expressions: &[Expression]) -> Result<Shape, String> {
+    if let Expression::Literal(literal) = &expressions[0] {
+        if let ValueType::SCALAR(ref s) = literal.value.data {
+            return match identity_size(s.data) {
+                Some(n) => Ok(Shape::Matrix(n, n)),
+                None => Err(identity_message()),
+            };
+        }
+    }
+    if args[0].maybe_scalar() {
+        Ok(Shape::AnyMatrix)
+    } else {
+        Err(identity_message())
+    }
+}
+
+fn reduced_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    match args[0] {
+        Shape::Scalar => Err("Cannot row reduce a scalar".to_string()),
+        _ => matrix_shape(&args[0]),
+    }
+}
+
+fn inverse_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    square_shape(&args[0], "invert")
+}
+
+fn roots_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    match args[0] {
+        Shape::Scalar => {
+            Err("Cannot find roots of a scalar, expected a coefficient vector".to_string())
+        }
+        Shape::Matrix(rows, cols) if rows != 1 && cols != 1 => Err(format!(
+            "Polynomial coefficients must be a row or column vector, not {}",
+            args[0]
+        )),
+        _ => matrix_shape(&args[0]).map(|_| Shape::AnyMatrix),
+    }
+}
+
+fn charpoly_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    match square_shape(&args[0], "find the characteristic polynomial of")? {
+        Shape::Matrix(n, _) => Ok(Shape::Matrix(1, n + 1)),
+        shape => Ok(shape),
+    }
+}
+
+fn diagonalize_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    let shape = square_shape(&args[0], "diagonalize")?;
+    Ok(Shape::Tuple(vec![shape.clone(), shape]))
+}
+
+fn jordan_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    square_shape(&args[0], "find the Jordan form of")
+}
+
+/// The rows and factors must be scalars, their values are checked at runtime.
+fn row_operation_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    for argument in &args[1..] {
+        if !argument.maybe_scalar() {
+            return Err(format!("Expected a scalar row or factor, not {}", argument));
+        }
+    }
+    if args[0].maybe_matrix() {
+        Ok(some_matrix(&args[0]))
+    } else {
+        Err("Row operations can only be applied to a matrix.".to_string())
+    }
+}
+
+fn elem_shape(args: &[Shape], expressions: &[Expression]) -> Result<Shape, String> {
+    let arity = match &args[1] {
+        Shape::Builtin(name) if name == "addrow" => 5,
+        Shape::Builtin(name) if is_row_operation(name) => 4,
+        Shape::Unknown => args.len(),
+        _ => return Err("Expect swap, scale or addrow as the operation of elem".to_string()),
+    };
+    if args.len() != arity {
+        return Err(format!(
+            "Expected a call of the form {}",
+            if arity == 5 {
+                "elem(n, addrow, target, source, k)"
+            } else {
+                "elem(n, swap, i, j) or elem(n, scale, i, k)"
+            }
+        ));
+    }
+    let size = identity_shape(&args[..1], &expressions[..1])?;
+    row_operation_shape(&[&[size], &args[2..]].concat(), &[])
+}
+
+/// map, reduce, rowmap and colmap take a matrix and a function of `arity`
+/// arguments.
+fn higher_order_shape(name: &str, arity: usize, args: &[Shape]) -> Result<(), String> {
+    match args[1] {
+        Shape::Function(n) if n != arity => {
+            return Err(format!(
+                "The function given to {} must take {} argument{}, not {}",
+                name,
+                arity,
+                if arity == 1 { "" } else { "s" },
+                n
+            ))
+        }
+        Shape::Function(_) | Shape::Builtin(_) | Shape::Unknown => (),
+        _ => {
+            return Err(format!(
+                "Expected a function as the second argument of {}, not {}",
+                name, args[1]
+            ))
+        }
+    }
+    if args[0].maybe_matrix() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expected a matrix as the first argument of {}, not {}",
+            name, args[0]
+        ))
+    }
+}
+
+fn map_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    higher_order_shape("map", 1, args)?;
+    Ok(some_matrix(&args[0]))
+}
+
+fn reduce_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    higher_order_shape("reduce", 2, args)?;
+    Ok(Shape::Unknown)
+}
+
+fn rowmap_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    higher_order_shape("rowmap", 1, args)?;
+    Ok(Shape::AnyMatrix)
+}
+
+fn colmap_shape(args: &[Shape], _: &[Expression]) -> Result<Shape, String> {
+    higher_order_shape("colmap", 1, args)?;
+    Ok(Shape::AnyMatrix)
+}
@@ -1,24 +1,116 @@
-
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 use crate::value::Value;
 
+/// A scope of variables. Lookups that miss fall through to the enclosing
+/// scope, ending at the globals.
 pub struct Environment {
     values: HashMap<String, Value>,
+    /// names defined with const in this scope
+    constants: HashSet<String>,
+    /// doc comments of the names in this scope that have one
+    docs: HashMap<String, String>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
 }
 
 impl Environment {
     pub fn new() -> Self {
         Environment {
-            values: HashMap::new()
+            values: HashMap::new(),
+            constants: HashSet::new(),
+            docs: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            constants: HashSet::new(),
+            docs: HashMap::new(),
+            enclosing: Some(enclosing),
         }
     }
 
     pub fn define(&mut self, name: String, value: Value) {
+        self.docs.remove(&name);
         self.values.insert(name, value);
     }
 
-    pub fn lookup(&self, name: &String) -> Option<&Value> {
-        self.values.get(name)
+    pub fn define_constant(&mut self, name: String, value: Value) {
+        self.docs.remove(&name);
+        self.constants.insert(name.clone());
+        self.values.insert(name, value);
+    }
+
+    /// Attaches a doc comment to a name defined in this scope.
+    pub fn document(&mut self, name: String, doc: String) {
+        self.docs.insert(name, doc);
+    }
+
+    /// The doc comment of the variable `name` refers to, if it has one.
+    pub fn doc(&self, name: &str) -> Option<String> {
+        if self.values.contains_key(name) {
+            return self.docs.get(name).cloned();
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().doc(name),
+            None => None,
+        }
     }
-}
\ No newline at end of file
+
+    /// Every documented name in this scope with its doc comment, sorted by name.
+    pub fn documented(&self) -> Vec<(String, String)> {
+        let mut docs: Vec<(String, String)> = self
+            .docs
+            .iter()
+            .map(|(name, doc)| (name.clone(), doc.clone()))
+            .collect();
+        docs.sort();
+        docs
+    }
+
+    /// The values defined in this scope, enclosing scopes are not included.
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.values.values()
+    }
+
+    /// Whether `name` is a constant of this scope, enclosing scopes are not checked.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    /// Changes an existing variable in the innermost scope that has it.
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), String> {
+        if self.values.contains_key(name) {
+            if self.constants.contains(name) {
+                return Err("Cannot assign to a constant.".to_string());
+            }
+            self.values.insert(name.to_string(), value);
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err("Undefined variable.".to_string()),
+        }
+    }
+
+    pub fn lookup(&self, name: &String) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.borrow().lookup(name),
+                None => None,
+            },
+        }
+    }
+}
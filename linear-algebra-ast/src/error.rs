@@ -0,0 +1,177 @@
+use std::fmt;
+
+use crate::tokens::Span;
+use crate::tokens::Token;
+use crate::tokens::TokenType;
+
+/// What a diagnostic is about, which decides how it is labelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A syntax error found while parsing. The parser keeps going after one
+    /// so that every error in a program is reported at once.
+    Parse,
+    /// A shape error found by the analyzer before the program runs, like
+    /// multiplying matrices whose sizes do not line up.
+    Shape,
+    /// A mistake in the format string of a print, like a placeholder with
+    /// no value for it.
+    Format,
+    /// An error raised while running a program. Execution of the current
+    /// program stops, but the interpreter and its variables are left intact.
+    Runtime,
+    /// Something allowed but likely a mistake. Warnings are printed without
+    /// stopping the program.
+    Warning,
+}
+
+impl Kind {
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Parse => "Error",
+            Kind::Shape => "Shape error",
+            Kind::Format => "Format error",
+            Kind::Runtime => "Runtime error",
+            Kind::Warning => "Warning",
+        }
+    }
+}
+
+/// An error or warning at a token of the source.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub kind: Kind,
+    pub token: Token,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(kind: Kind, token: &Token, message: &str) -> Self {
+        Diagnostic {
+            kind,
+            token: token.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn parse(token: &Token, message: &str) -> Self {
+        Diagnostic::new(Kind::Parse, token, message)
+    }
+
+    pub fn shape(token: &Token, message: &str) -> Self {
+        Diagnostic::new(Kind::Shape, token, message)
+    }
+
+    pub fn format(token: &Token, message: &str) -> Self {
+        Diagnostic::new(Kind::Format, token, message)
+    }
+
+    pub fn runtime(token: &Token, message: &str) -> Self {
+        Diagnostic::new(Kind::Runtime, token, message)
+    }
+
+    pub fn warning(token: &Token, message: &str) -> Self {
+        Diagnostic::new(Kind::Warning, token, message)
+    }
+
+    /// The diagnostic followed by the offending line of `source`.
+    pub fn report(&self, source: &str) -> String {
+        format!("{}\n{}", self, underline(source, &self.token.span))
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}: {}",
+            self.kind.label(),
+            location(&self.token),
+            self.message
+        )
+    }
+}
+
+/// Whatever stopped a program run through `Interpreter::eval_str`. Parse
+/// and shape errors are all reported at once, before anything runs.
+#[derive(Debug)]
+pub enum Error {
+    Parse(Vec<Diagnostic>),
+    Analysis(Vec<Diagnostic>),
+    Runtime(Diagnostic),
+}
+
+impl Error {
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Error::Parse(errors) | Error::Analysis(errors) => errors,
+            Error::Runtime(error) => std::slice::from_ref(error),
+        }
+    }
+
+    /// Every error followed by its offending line of `source`.
+    pub fn report(&self, source: &str) -> String {
+        join(self.diagnostics().iter().map(|error| error.report(source)))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            join(self.diagnostics().iter().map(|e| e.to_string()))
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn join(lines: impl Iterator<Item = String>) -> String {
+    lines.collect::<Vec<String>>().join("\n")
+}
+
+/// Names a token and where it is, e.g. `'+' (line 2, column 7)`.
+fn location(token: &Token) -> String {
+    let name = match token.token_type {
+        TokenType::TOKEN_EOF => "end".to_string(),
+        // the lexeme of an error token is the message, not source text
+        TokenType::TOKEN_ERROR => {
+            return format!("line {}, column {}", token.span.line, token.span.column)
+        }
+        _ => format!("'{}'", token.lexeme.escape_debug()),
+    };
+    format!(
+        "{} (line {}, column {})",
+        name, token.span.line, token.span.column
+    )
+}
+
+/// Prints the source line containing `span` with a caret under the span.
+///
+/// ```text
+/// 2 | print a * b;
+///   |         ^
+/// ```
+fn underline(source: &str, span: &Span) -> String {
+    let text = source.lines().nth(span.line - 1).unwrap_or("");
+    let gutter = span.line.to_string();
+    // keep tabs so the caret lines up with the text above it
+    let indent: String = text
+        .chars()
+        .take(span.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    // offsets are in bytes, the caret needs one ^ per character
+    let width = source
+        .get(span.start..span.end)
+        .map_or(0, |text| text.chars().count())
+        .max(1);
+    format!(
+        "{} | {}\n{} | {}{}",
+        gutter,
+        text,
+        " ".repeat(gutter.len()),
+        indent,
+        "^".repeat(width)
+    )
+}
@@ -0,0 +1,173 @@
+use crate::settings::Settings;
+use crate::value::Value;
+
+/// Part of a format string like `"det = {:.3}"`.
+pub enum Piece {
+    Text(String),
+    /// a `{}` filled in by the next value
+    Value(Spec),
+}
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How a placeholder lays out its value, as in Rust: `{:>8.2}` right aligns
+/// in 8 characters with 2 decimals. Matrices apply it to every entry.
+#[derive(Clone, Copy, Default)]
+pub struct Spec {
+    pub align: Option<Align>,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+}
+
+impl Spec {
+    /// Reads what follows the `:` of a placeholder.
+    fn parse(spec: &str) -> Option<Spec> {
+        let mut chars = spec.chars().peekable();
+        let align = match chars.peek() {
+            Some('<') => Some(Align::Left),
+            Some('^') => Some(Align::Center),
+            Some('>') => Some(Align::Right),
+            _ => None,
+        };
+        if align.is_some() {
+            chars.next();
+        }
+        let rest: String = chars.collect();
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision.parse().ok()?)),
+            None => (rest.as_str(), None),
+        };
+        let width = match width {
+            "" => None,
+            width => Some(width.parse().ok()?),
+        };
+        Some(Spec {
+            align,
+            width,
+            precision,
+        })
+    }
+
+    /// Lays out `value` as print shows it, a precision given here is a
+    /// number of decimals and takes the place of the precision setting.
+    pub fn format(&self, value: &Value, settings: &Settings) -> String {
+        let value = match self.precision {
+            Some(decimals) => value.rounded_decimals(settings, decimals),
+            None => value.rounded(settings),
+        };
+        let width = self.width.unwrap_or(0);
+        match (self.align, self.precision) {
+            (None, None) => format!("{:width$}", value),
+            (None, Some(precision)) => format!("{:width$.precision$}", value),
+            (Some(Align::Left), None) => format!("{:<width$}", value),
+            (Some(Align::Left), Some(precision)) => format!("{:<width$.precision$}", value),
+            (Some(Align::Center), None) => format!("{:^width$}", value),
+            (Some(Align::Center), Some(precision)) => format!("{:^width$.precision$}", value),
+            (Some(Align::Right), None) => format!("{:>width$}", value),
+            (Some(Align::Right), Some(precision)) => format!("{:>width$.precision$}", value),
+        }
+    }
+}
+
+/// Splits a format string into text and placeholders. `{{` and `}}` stand
+/// for literal braces.
+pub fn parse(format: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(c);
+            }
+            ('{', _) => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err("Use {} for a value, {{ and }} for literal braces".to_string());
+                }
+                let spec = match placeholder.strip_prefix(':') {
+                    Some(spec) => Spec::parse(spec),
+                    None if placeholder.is_empty() => Some(Spec::default()),
+                    None => None,
+                };
+                let Some(spec) = spec else {
+                    return Err(format!(
+                        "Unknown placeholder {{{}}}, use {{}} or a layout like {{:>8.3}}",
+                        placeholder
+                    ));
+                };
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Value(spec));
+            }
+            ('}', _) => return Err("Use {} for a value, {{ and }} for literal braces".to_string()),
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+pub fn placeholders(pieces: &[Piece]) -> usize {
+    pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Value(_)))
+        .count()
+}
+
+/// Fills the placeholders in order. Values that span several lines, like
+/// matrices, start on a line of their own and the text after them continues
+/// on the next line.
+pub fn render(pieces: &[Piece], values: &[Value], settings: &Settings) -> String {
+    let mut output = String::new();
+    let mut values = values.iter();
+    let mut after_block = false;
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => {
+                if after_block {
+                    let text = text.trim_start();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    output.push('\n');
+                    output.push_str(text);
+                } else {
+                    output.push_str(text);
+                }
+                after_block = false;
+            }
+            Piece::Value(spec) => {
+                let value = values
+                    .next()
+                    .map(|value| spec.format(value, settings))
+                    .unwrap_or_default();
+                let block = value.contains('\n');
+                if (block || after_block) && !output.is_empty() {
+                    output.truncate(output.trim_end_matches(' ').len());
+                    output.push('\n');
+                }
+                output.push_str(&value);
+                after_block = block;
+            }
+        }
+    }
+    output
+}
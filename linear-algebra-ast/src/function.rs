@@ -0,0 +1,22 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ast::statement::Statement;
+use crate::environment::Environment;
+use crate::tokens::Token;
+
+/// A user defined function, `fn name(params) = expr;` or
+/// `fn name(params) { ... return expr; }`.
+pub struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Rc<Vec<Statement>>,
+    /// the scope the function was declared in
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl Function {
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+}
@@ -1,149 +1,548 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::analyzer::Analyzer;
+use crate::analyzer::Shape;
 use crate::ast::astprinter::ASTPrinter;
 use crate::ast::expression::Binary;
+use crate::ast::expression::Call;
+use crate::ast::expression::Expression;
 use crate::ast::expression::ExpressionType;
 use crate::ast::expression::ExpressionVisitor;
 use crate::ast::expression::Grouping;
 use crate::ast::expression::Identifier;
+use crate::ast::expression::Lambda;
 use crate::ast::expression::Literal;
+use crate::ast::expression::Logical;
 use crate::ast::expression::Unary;
+use crate::ast::statement::AssignStatement;
+use crate::ast::statement::ExplainStatement;
 use crate::ast::statement::ExpressionStatement;
+use crate::ast::statement::ForStatement;
+use crate::ast::statement::FunctionStatement;
+use crate::ast::statement::IfStatement;
 use crate::ast::statement::LetStatement;
 use crate::ast::statement::NewLineStatement;
 use crate::ast::statement::PrintStatement;
+use crate::ast::statement::ReturnStatement;
+use crate::ast::statement::SetStatement;
 use crate::ast::statement::Statement;
 use crate::ast::statement::StatementType;
 use crate::ast::statement::StatementVisitor;
+use crate::ast::statement::WhileStatement;
+use crate::builtins::BuiltinRegistry;
 use crate::environment::Environment;
+use crate::error::Diagnostic;
+use crate::error::Error;
+use crate::format;
+use crate::function::Function;
+use crate::lexer::Lexer;
+use crate::native::Arguments;
+use crate::native::NativeFunction;
+use crate::parser::Parser;
+use crate::settings::Settings;
+use crate::tokens::Token;
 use crate::tokens::TokenType;
+use crate::value::nearly_equal;
+use crate::value::Matrix;
+use crate::value::RowOperation;
 use crate::value::Value;
 use crate::value::ValueType;
 
 pub struct Interpreter {
     pub ast_printer: ASTPrinter,
-    pub globals: Environment,
+    pub globals: Rc<RefCell<Environment>>,
+    /// the innermost scope, the globals unless a function is running
+    environment: Rc<RefCell<Environment>>,
+    /// print every elementary row operation performed by ref, rref and inv
+    pub explain: bool,
+    /// warn when a let statement shadows an existing variable
+    pub warn_shadowing: bool,
+    pub settings: Settings,
+    /// functions scripts can call without defining them
+    pub builtins: BuiltinRegistry,
+    /// where print, newline and explain write, stdout unless replaced
+    pub output: Box<dyn Write>,
+    /// where warnings and errors are reported, stderr unless replaced
+    pub diagnostics: Box<dyn Write>,
+    /// set by a return statement until the function call picks it up
+    returning: Option<Value>,
+    call_depth: usize,
+}
+
+/// Deep enough for any reasonable recursion, shallow enough to report runaway
+/// recursion as an error instead of overflowing the stack.
+const MAX_CALL_DEPTH: usize = 200;
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let mut prelude = Environment::new();
+        for (name, value, doc) in [
+            (
+                "pi",
+                std::f64::consts::PI,
+                "The ratio of a circle's circumference to its diameter.",
+            ),
+            (
+                "e",
+                std::f64::consts::E,
+                "Euler's number, the base of the natural logarithm.",
+            ),
+            ("inf", f64::INFINITY, "Positive infinity."),
+            (
+                "nan",
+                f64::NAN,
+                "Not a number, it is not equal to anything including itself.",
+            ),
+        ] {
+            // plain variables rather than constants so scripts can still use the names
+            prelude.define(name.to_string(), Value::new_scalar(value));
+            prelude.document(name.to_string(), doc.to_string());
+        }
+        let globals = Rc::new(RefCell::new(prelude));
         Interpreter {
             ast_printer: ASTPrinter::new(),
-            globals: Environment::new(),
+            environment: Rc::clone(&globals),
+            globals,
+            explain: false,
+            warn_shadowing: false,
+            settings: Settings::new(),
+            builtins: BuiltinRegistry::standard(),
+            output: Box::new(std::io::stdout()),
+            diagnostics: Box::new(std::io::stderr()),
+            returning: None,
+            call_depth: 0,
+        }
+    }
+
+    pub fn with_settings(settings: Settings) -> Self {
+        Interpreter {
+            settings,
+            ..Interpreter::new()
+        }
+    }
+    /// Makes a Rust closure callable from scripts as `name(...)`, e.g.
+    /// `interpreter.register("loadSensor", |args| ...)`. It hides any builtin
+    /// of the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        function: impl Fn(&Arguments) -> Result<Value, String> + 'static,
+    ) {
+        self.register_native(name, function);
+    }
+
+    /// Like `register`, for functions that also give their arity and usage.
+    pub fn register_native(&mut self, name: &str, function: impl NativeFunction + 'static) {
+        let owned = name.to_string();
+        let arity = function.arity();
+        let usage = function.usage(name);
+        // what a native function returns is only known once it runs
+        self.builtins.register(
+            name,
+            arity,
+            &usage,
+            |_, _| Ok(Shape::Unknown),
+            move |_, token, args| {
+                function
+                    .call(&Arguments::new(&owned, args))
+                    .map_err(|message| Diagnostic::runtime(token, &message))
+            },
+        );
+    }
+
+    /// Runs a row reduction, printing each step along the way when explaining.
+    pub(crate) fn row_reduce<T>(
+        &mut self,
+        token: &Token,
+        matrix: &mut Matrix,
+        reduction: impl FnOnce(&mut Matrix, f64, &mut dyn FnMut(RowOperation, &Matrix)) -> T,
+    ) -> Result<T, Diagnostic> {
+        let tolerance = self.settings.tolerance;
+        if !self.explain {
+            return Ok(reduction(matrix, tolerance, &mut |_, _| {}));
+        }
+        // a step that cannot be printed does not stop the reduction, the
+        // first failure is reported once it is done
+        let mut failure = None;
+        let result = reduction(matrix, tolerance, &mut |operation, step| {
+            if failure.is_none() {
+                failure = self.print_step(token, operation, step).err();
+            }
+        });
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    pub(crate) fn print_step(
+        &mut self,
+        token: &Token,
+        operation: RowOperation,
+        matrix: &Matrix,
+    ) -> Result<(), Diagnostic> {
+        let matrix = Value::wrap_matrix(matrix.clone()).to_text(&self.settings);
+        let operation = operation.rounded(self.settings.precision);
+        self.write_output(token, &format!("{}\n{}\n", operation, matrix))
+    }
+
+    /// Writes a line of program output. A sink that fails stops the program
+    /// with a runtime error at `token`.
+    fn write_output(&mut self, token: &Token, text: &str) -> Result<(), Diagnostic> {
+        writeln!(self.output, "{}", text).map_err(|error| {
+            Diagnostic::runtime(token, &format!("Could not write the output: {}", error))
+        })
+    }
+
+    /// Reports a warning or error on the diagnostics sink.
+    pub fn diagnose(&mut self, text: &str) -> std::io::Result<()> {
+        writeln!(self.diagnostics, "{}", text)
+    }
+
+    /// Operators only apply to scalars and matrices.
+    fn check_operand(&self, operator: &Token, value: &Value) -> Result<(), Diagnostic> {
+        match value.data {
+            ValueType::TUPLE(_) => Err(Diagnostic::runtime(
+                operator,
+                "Cannot apply an operator to a tuple, unpack it with let first.",
+            )),
+            ValueType::FUNCTION(_) | ValueType::BUILTIN(_) => Err(Diagnostic::runtime(
+                operator,
+                "Cannot apply an operator to a function, call it first.",
+            )),
+            ValueType::BOOL(_) => Err(Diagnostic::runtime(
+                operator,
+                "Cannot apply an operator to a boolean.",
+            )),
+            ValueType::STRING(_) => Err(Diagnostic::runtime(
+                operator,
+                "Cannot apply an operator to a string.",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// ==, !=, <, <=, > and >=. For == and != values closer together than
+    /// the tolerance count as equal, so rounding error does not break them.
+    /// Ordering is exact, a tolerance there would misorder small values.
+    fn compare(&self, operator: &Token, left: &Value, right: &Value) -> Result<Value, Diagnostic> {
+        let tolerance = self.settings.tolerance;
+        if let TokenType::TOKEN_EQUAL_EQUAL | TokenType::TOKEN_BANG_EQUAL = operator.token_type {
+            for value in [left, right] {
+                if !matches!(value.data, ValueType::BOOL(_) | ValueType::STRING(_)) {
+                    self.check_operand(operator, value)?;
+                }
+            }
+            // values of different kinds or sizes are never equal
+            let equal = match (&left.data, &right.data) {
+                (ValueType::SCALAR(a), ValueType::SCALAR(b)) => {
+                    nearly_equal(a.data, b.data, tolerance)
+                }
+                (ValueType::MATRIX(a), ValueType::MATRIX(b)) => a.approx_eq(b, tolerance),
+                (ValueType::BOOL(a), ValueType::BOOL(b)) => a == b,
+                (ValueType::STRING(a), ValueType::STRING(b)) => a == b,
+                _ => false,
+            };
+            return Ok(Value::new_bool(
+                equal == (operator.token_type == TokenType::TOKEN_EQUAL_EQUAL),
+            ));
+        }
+        let (a, b) = match (&left.data, &right.data) {
+            (ValueType::SCALAR(a), ValueType::SCALAR(b)) => (a.data, b.data),
+            _ => {
+                return Err(Diagnostic::runtime(
+                    operator,
+                    "Only scalars can be ordered, use == to compare other values.",
+                ))
+            }
+        };
+        let result = match operator.token_type {
+            TokenType::TOKEN_LESS => a < b,
+            TokenType::TOKEN_LESS_EQUAL => a <= b,
+            TokenType::TOKEN_GREATER => a > b,
+            _ => a >= b,
+        };
+        Ok(Value::new_bool(result))
+    }
+
+    /// Whether a condition holds, it has to be a boolean.
+    fn is_true(&self, token: &Token, value: &Value) -> Result<bool, Diagnostic> {
+        match value.data {
+            ValueType::BOOL(b) => Ok(b),
+            _ => Err(Diagnostic::runtime(token, "Expected a boolean.")),
+        }
+    }
+
+    /// Defines a variable in the current scope, where a constant of the same
+    /// name cannot be redefined.
+    fn declare(&mut self, name: &Token, value: Value, constant: bool) -> Result<(), Diagnostic> {
+        let mut environment = self.environment.borrow_mut();
+        if environment.is_constant(&name.lexeme) {
+            return Err(Diagnostic::runtime(name, "Cannot redefine a constant."));
         }
+        if constant {
+            environment.define_constant(name.lexeme.to_string(), value);
+        } else {
+            environment.define(name.lexeme.to_string(), value);
+        }
+        Ok(())
     }
-    fn runtime_error(&self, message: &str) {
-        println!("Runtime error at {}", message);
-        std::process::exit(1);
+
+    /// Runs statements in the current scope until they finish or one returns.
+    fn execute_statements(&mut self, statements: &[Statement]) -> Result<(), Diagnostic> {
+        for statement in statements {
+            statement.visit(self)?;
+            if self.returning.is_some() {
+                break;
+            }
+        }
+        Ok(())
     }
-    pub fn interpret(&mut self, stmts: Vec<Statement>) {
+
+    /// A scope inside the current one for the body of an if, while or for,
+    /// so names defined there are gone once the body finishes.
+    fn nested_environment(&self) -> Environment {
+        Environment::with_enclosing(Rc::clone(&self.environment))
+    }
+
+    /// Runs statements in a new scope until they finish or one returns.
+    fn execute_block(
+        &mut self,
+        statements: &[Statement],
+        environment: Environment,
+    ) -> Result<(), Diagnostic> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+        let result = self.execute_statements(statements);
+        self.environment = previous;
+        result
+    }
+
+    /// Calls a function value, reporting errors at `token`.
+    fn call_function(
+        &mut self,
+        function: &Function,
+        arguments: Vec<Value>,
+        token: &Token,
+    ) -> Result<Value, Diagnostic> {
+        if arguments.len() != function.arity() {
+            return Err(Diagnostic::runtime(
+                token,
+                &format!(
+                    "Expected {} arguments but got {}.",
+                    function.arity(),
+                    arguments.len()
+                ),
+            ));
+        }
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(Diagnostic::runtime(
+                token,
+                "Too much recursion, function calls are nested too deeply.",
+            ));
+        }
+        let mut environment = Environment::with_enclosing(Rc::clone(&function.closure));
+        for (param, argument) in function.params.iter().zip(arguments) {
+            environment.define(param.lexeme.to_string(), argument);
+        }
+
+        self.call_depth += 1;
+        let result = self.execute_block(&function.body, environment);
+        self.call_depth -= 1;
+        let value = self.returning.take();
+        result?;
+        match value {
+            Some(value) => Ok(value),
+            None => Err(Diagnostic::runtime(
+                &function.name,
+                "Function ended without returning a value.",
+            )),
+        }
+    }
+
+    /// Calls a function or builtin value, reporting errors at `token`.
+    pub(crate) fn call_value(
+        &mut self,
+        callee: &Value,
+        arguments: Vec<Value>,
+        token: &Token,
+    ) -> Result<Value, Diagnostic> {
+        match &callee.data {
+            ValueType::FUNCTION(function) => self.call_function(function, arguments, token),
+            ValueType::BUILTIN(builtin) => {
+                builtin
+                    .check_arity(arguments.len())
+                    .map_err(|message| Diagnostic::runtime(token, &message))?;
+                (builtin.call)(self, token, arguments)
+            }
+            _ => Err(Diagnostic::runtime(token, "Can only call functions.")),
+        }
+    }
+
+    /// Runs statements in order, stopping at the first runtime error.
+    /// Variables defined before the error are kept.
+    pub fn interpret(&mut self, stmts: Vec<Statement>) -> Result<(), Diagnostic> {
         for statement in stmts {
-            statement.visit(self);
+            statement.visit(self)?;
+        }
+        Ok(())
+    }
+
+    /// Lexes, checks and runs `source` against the variables defined so far.
+    /// The result is the value of a final expression statement, whose `;` can
+    /// be left out as in `inv(A)`, and an empty tuple when the program ends in
+    /// anything else.
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, Error> {
+        let mut lexer = Lexer::new_empty();
+        lexer.content = source.to_string();
+        let mut statements = Parser::new(lexer.scan_tokens())
+            .trailing_expression(true)
+            .parse()
+            .map_err(Error::Parse)?;
+        let (result, warnings) = {
+            let globals = self.globals.borrow();
+            let mut analyzer =
+                Analyzer::new(&globals, &self.builtins).warn_shadowing(self.warn_shadowing);
+            (analyzer.analyze(&statements), analyzer.warnings)
+        };
+        for warning in warnings {
+            self.diagnose(&warning.report(source)).map_err(|error| {
+                Error::Runtime(Diagnostic::runtime(
+                    &warning.token,
+                    &format!("Could not write the warning: {}", error),
+                ))
+            })?;
+        }
+        result.map_err(Error::Analysis)?;
+        let last = match statements.last() {
+            Some(Statement::Expression(_)) => statements.pop(),
+            _ => None,
+        };
+        self.interpret(statements).map_err(Error::Runtime)?;
+        match last {
+            Some(Statement::Expression(statement)) => {
+                statement.expression.visit(self).map_err(Error::Runtime)
+            }
+            _ => Ok(Value::wrap_tuple(Vec::new())),
         }
     }
+
+    /// The global variable `name`, if scripts or `set` have defined it.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().lookup(&name.to_string())
+    }
+
+    /// Defines or replaces the global variable `name`, e.g. with a matrix from
+    /// `Value::try_from(rows)`. Constants cannot be replaced.
+    pub fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        let mut globals = self.globals.borrow_mut();
+        if globals.is_constant(name) {
+            return Err(format!("Cannot assign to the constant {}.", name));
+        }
+        globals.define(name.to_string(), value);
+        Ok(())
+    }
 }
 
-impl ExpressionVisitor<Value> for Interpreter {
-    fn visit_literal(&mut self, literal: &Literal) -> Value {
-        literal.value.clone()
+impl ExpressionVisitor<Result<Value, Diagnostic>> for Interpreter {
+    fn visit_literal(&mut self, literal: &Literal) -> Result<Value, Diagnostic> {
+        Ok(literal.value.clone())
     }
 
-    fn visit_identifier(&mut self, identifier: &Identifier) -> Value {
-        let value = self.globals.lookup(&identifier.name);
+    fn visit_identifier(&mut self, identifier: &Identifier) -> Result<Value, Diagnostic> {
+        let value = self.environment.borrow().lookup(&identifier.name.lexeme);
+        // variables hide builtins of the same name
         match value {
-            Some(value) => value.clone(),
-            None => {
-                self.runtime_error("Undefined variable.");
-                Value::new_scalar(0.0)
-            }
+            Some(value) => Ok(value),
+            None => match self.builtins.get(&identifier.name.lexeme) {
+                Some(builtin) => Ok(Value::wrap_builtin(builtin)),
+                None => Err(Diagnostic::runtime(&identifier.name, "Undefined variable.")),
+            },
         }
     }
 
-    fn visit_unary(&mut self, unary: &Unary) -> Value {
-        let right = unary.right.visit(self);
+    fn visit_unary(&mut self, unary: &Unary) -> Result<Value, Diagnostic> {
+        let right = unary.right.visit(self)?;
+        if unary.operator.token_type == TokenType::TOKEN_BANG {
+            return match right.data {
+                ValueType::BOOL(b) => Ok(Value::new_bool(!b)),
+                _ => Err(Diagnostic::runtime(
+                    &unary.operator,
+                    "Expected a boolean after '!'.",
+                )),
+            };
+        }
+        self.check_operand(&unary.operator, &right)?;
         match unary.operator.token_type {
             TokenType::TOKEN_MINUS => match right.data {
-                ValueType::SCALAR(s) => Value::new_scalar(s.data * -1.0),
+                ValueType::SCALAR(s) => Ok(Value::new_scalar(-s.data)),
                 ValueType::MATRIX(m) => {
                     let mut new_matrix = m.clone();
                     new_matrix.scale(-1.0);
-                    Value::wrap_matrix(new_matrix)
+                    Ok(Value::wrap_matrix(new_matrix))
                 }
+                _ => unreachable!(),
             },
             TokenType::TOKEN_TRANSPOSE => match right.data {
-                ValueType::SCALAR(_) => {
-                    self.runtime_error("Cannot transpose a scalar.");
-                    Value::new_scalar(0.0)
-                }
+                ValueType::SCALAR(_) => Err(Diagnostic::runtime(
+                    &unary.operator,
+                    "Cannot transpose a scalar.",
+                )),
                 ValueType::MATRIX(m) => {
                     let mut new_matrix = m.clone();
                     new_matrix.transpose();
-                    Value::wrap_matrix(new_matrix)
-                }
-            },
-            TokenType::TOKEN_REF => match right.data {
-                ValueType::SCALAR(_) => {
-                    self.runtime_error("Cannot convert scalar to REF matrix");
-                    Value::new_scalar(0.0)
-                }
-                ValueType::MATRIX(m) => {
-                    let mut new_matrix = m.clone();
-                    new_matrix.ref_matrix();
-                    Value::wrap_matrix(new_matrix)
-                }
-            },
-            TokenType::TOKEN_RREF => match right.data {
-                ValueType::SCALAR(_) => {
-                    self.runtime_error("Cannot convert scalar to RREF matrix");
-                    Value::new_scalar(0.0)
-                }
-                ValueType::MATRIX(m) => {
-                    let mut new_matrix = m.clone();
-                    new_matrix.rref_matrix();
-                    Value::wrap_matrix(new_matrix)
-                }
-            },
-            
-            TokenType::TOKEN_INVERSE => match right.data {
-                ValueType::SCALAR(_) => {
-                    self.runtime_error("Cannot convert scalar to RREF matrix");
-                    Value::new_scalar(0.0)
-                }
-                ValueType::MATRIX(m) => {
-                    let mut new_matrix = m.clone();
-                    if new_matrix.inverse() {
-                        Value::wrap_matrix(new_matrix)
-                    } else {
-                        self.runtime_error("Matrix is not invertible");
-                        Value::new_scalar(0.0)
-                    }
+                    Ok(Value::wrap_matrix(new_matrix))
                 }
+                _ => unreachable!(),
             },
-            _ => {
-                self.runtime_error("Invalid unary operator");
-                Value::new_scalar(0.0)
-            }
+            _ => Err(Diagnostic::runtime(
+                &unary.operator,
+                "Invalid unary operator",
+            )),
         }
     }
-    fn visit_binary(&mut self, binary: &Binary) -> Value {
-        let left = binary.left.visit(self);
-        let right = binary.right.visit(self);
+    fn visit_binary(&mut self, binary: &Binary) -> Result<Value, Diagnostic> {
+        let left = binary.left.visit(self)?;
+        let right = binary.right.visit(self)?;
+        if let TokenType::TOKEN_EQUAL_EQUAL
+        | TokenType::TOKEN_BANG_EQUAL
+        | TokenType::TOKEN_LESS
+        | TokenType::TOKEN_LESS_EQUAL
+        | TokenType::TOKEN_GREATER
+        | TokenType::TOKEN_GREATER_EQUAL = binary.operator.token_type
+        {
+            return self.compare(&binary.operator, &left, &right);
+        }
+        self.check_operand(&binary.operator, &left)?;
+        self.check_operand(&binary.operator, &right)?;
         match binary.operator.token_type {
             TokenType::TOKEN_PLUS => match left.data {
                 ValueType::SCALAR(s) => match right.data {
-                    ValueType::SCALAR(s2) => Value::new_scalar(s.data + s2.data),
-                    ValueType::MATRIX(_m) => {
-                        self.runtime_error("Cannot add a scalar to a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::SCALAR(s2) => Ok(Value::new_scalar(s.data + s2.data)),
+                    ValueType::MATRIX(_m) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot add a scalar to a matrix",
+                    )),
+                    _ => unreachable!(),
                 },
                 ValueType::MATRIX(m) => match right.data {
-                    ValueType::SCALAR(_s) => {
-                        self.runtime_error("Cannot add a scalar to a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::SCALAR(_s) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot add a scalar to a matrix",
+                    )),
                     ValueType::MATRIX(m2) => {
                         if m.rows != m2.rows || m.cols != m2.cols {
-                            self.runtime_error("Cannot add matrices of different sizes");
-                            Value::new_scalar(0.0)
+                            Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot add matrices of different sizes",
+                            ))
                         } else {
                             let mut new_matrix = m.clone();
                             for i in 0..m.rows {
@@ -151,28 +550,37 @@ impl ExpressionVisitor<Value> for Interpreter {
                                     new_matrix.data[i * m.cols + j] += m2.data[i * m.cols + j];
                                 }
                             }
-                            Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
+                            Ok(Value::new_matrix(
+                                new_matrix.data,
+                                new_matrix.rows,
+                                new_matrix.cols,
+                            ))
                         }
                     }
+                    _ => unreachable!(),
                 },
+                _ => unreachable!(),
             },
             TokenType::TOKEN_MINUS => match left.data {
                 ValueType::SCALAR(s) => match right.data {
-                    ValueType::SCALAR(s2) => Value::new_scalar(s.data - s2.data),
-                    ValueType::MATRIX(_m) => {
-                        self.runtime_error("Cannot subtract a matrix from a scalar");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::SCALAR(s2) => Ok(Value::new_scalar(s.data - s2.data)),
+                    ValueType::MATRIX(_m) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot subtract a matrix from a scalar",
+                    )),
+                    _ => unreachable!(),
                 },
                 ValueType::MATRIX(m) => match right.data {
-                    ValueType::SCALAR(_s) => {
-                        self.runtime_error("Cannot subtract a scalar from a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::SCALAR(_s) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot subtract a scalar from a matrix",
+                    )),
                     ValueType::MATRIX(m2) => {
                         if m.rows != m2.rows || m.cols != m2.cols {
-                            self.runtime_error("Cannot subtract matrices of different sizes");
-                            Value::new_scalar(0.0)
+                            Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot subtract matrices of different sizes",
+                            ))
                         } else {
                             let mut new_matrix = m.clone();
                             for i in 0..m.rows {
@@ -180,14 +588,20 @@ impl ExpressionVisitor<Value> for Interpreter {
                                     new_matrix.data[i * m.cols + j] -= m2.data[i * m.cols + j];
                                 }
                             }
-                            Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
+                            Ok(Value::new_matrix(
+                                new_matrix.data,
+                                new_matrix.rows,
+                                new_matrix.cols,
+                            ))
                         }
                     }
+                    _ => unreachable!(),
                 },
+                _ => unreachable!(),
             },
             TokenType::TOKEN_STAR => match left.data {
                 ValueType::SCALAR(s) => match right.data {
-                    ValueType::SCALAR(s2) => Value::new_scalar(s.data * s2.data),
+                    ValueType::SCALAR(s2) => Ok(Value::new_scalar(s.data * s2.data)),
                     ValueType::MATRIX(m) => {
                         let mut new_matrix = m.clone();
                         for i in 0..m.rows {
@@ -195,8 +609,13 @@ impl ExpressionVisitor<Value> for Interpreter {
                                 new_matrix.data[i * m.cols + j] *= s.data;
                             }
                         }
-                        Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
+                        Ok(Value::new_matrix(
+                            new_matrix.data,
+                            new_matrix.rows,
+                            new_matrix.cols,
+                        ))
                     }
+                    _ => unreachable!(),
                 },
                 ValueType::MATRIX(m) => match right.data {
                     ValueType::SCALAR(s) => {
@@ -206,12 +625,18 @@ impl ExpressionVisitor<Value> for Interpreter {
                                 new_matrix.data[i * m.cols + j] *= s.data;
                             }
                         }
-                        Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
+                        Ok(Value::new_matrix(
+                            new_matrix.data,
+                            new_matrix.rows,
+                            new_matrix.cols,
+                        ))
                     }
                     ValueType::MATRIX(m2) => {
                         if m.cols != m2.rows {
-                            self.runtime_error("Cannot multiply matrices of different sizes");
-                            Value::new_scalar(0.0)
+                            Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot multiply matrices of different sizes",
+                            ))
                         } else {
                             let mut new_matrix = vec![0.0; m.rows * m2.cols];
                             for i in 0..m.rows {
@@ -222,31 +647,38 @@ impl ExpressionVisitor<Value> for Interpreter {
                                     }
                                 }
                             }
-                            Value::new_matrix(new_matrix, m.rows, m2.cols)
+                            Ok(Value::new_matrix(new_matrix, m.rows, m2.cols))
                         }
                     }
+                    _ => unreachable!(),
                 },
+                _ => unreachable!(),
             },
             TokenType::TOKEN_SLASH => match left.data {
                 ValueType::SCALAR(s) => match right.data {
                     ValueType::SCALAR(s2) => {
                         if s2.data == 0.0 {
-                            self.runtime_error("Cannot divide by zero");
-                            Value::new_scalar(0.0)
+                            Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot divide by zero",
+                            ))
                         } else {
-                            Value::new_scalar(s.data / s2.data)
+                            Ok(Value::new_scalar(s.data / s2.data))
                         }
                     }
-                    ValueType::MATRIX(_m) => {
-                        self.runtime_error("Cannot divide a scalar by a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::MATRIX(_m) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot divide a scalar by a matrix",
+                    )),
+                    _ => unreachable!(),
                 },
                 ValueType::MATRIX(m) => match right.data {
                     ValueType::SCALAR(s) => {
                         if s.data == 0.0 {
-                            self.runtime_error("Cannot divide by zero");
-                            Value::new_scalar(0.0)
+                            Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot divide by zero",
+                            ))
                         } else {
                             let mut new_matrix = m.clone();
                             for i in 0..m.rows {
@@ -254,36 +686,49 @@ impl ExpressionVisitor<Value> for Interpreter {
                                     new_matrix.data[i * m.cols + j] /= s.data;
                                 }
                             }
-                            Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
+                            Ok(Value::new_matrix(
+                                new_matrix.data,
+                                new_matrix.rows,
+                                new_matrix.cols,
+                            ))
                         }
                     }
-                    ValueType::MATRIX(_m2) => {
-                        self.runtime_error("Cannot divide a matrix by a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::MATRIX(_m2) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot divide a matrix by a matrix",
+                    )),
+                    _ => unreachable!(),
                 },
+                _ => unreachable!(),
             },
             TokenType::TOKEN_CARET => match left.data {
                 ValueType::SCALAR(s) => match right.data {
-                    ValueType::SCALAR(s2) => Value::new_scalar(s.data.powf(s2.data)),
-                    ValueType::MATRIX(_m) => {
-                        self.runtime_error("Cannot raise a scalar to a matrix");
-                        Value::new_scalar(0.0)
-                    }
+                    ValueType::SCALAR(s2) => Ok(Value::new_scalar(s.data.powf(s2.data))),
+                    ValueType::MATRIX(_m) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot raise a scalar to a matrix",
+                    )),
+                    _ => unreachable!(),
                 },
                 ValueType::MATRIX(m) => match right.data {
                     ValueType::SCALAR(s) => {
                         if m.rows != m.cols {
-                            self.runtime_error("Cannot raise a singular matrix to a power");
-                            return Value::new_scalar(0.0);
+                            return Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot raise a singular matrix to a power",
+                            ));
                         }
                         if s.data < 1.0 {
-                            self.runtime_error("Cannot raise a matrix to a power less than 1");
-                            return Value::new_scalar(0.0);
+                            return Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot raise a matrix to a power less than 1",
+                            ));
                         }
                         if s.data != (s.data as u32) as f64 {
-                            self.runtime_error("Cannot raise a matrix to a non-integer power");
-                            return Value::new_scalar(0.0);
+                            return Err(Diagnostic::runtime(
+                                &binary.operator,
+                                "Cannot raise a matrix to a non-integer power",
+                            ));
                         }
                         let power: u32 = s.data as u32;
                         let mut new_matrix = m.clone();
@@ -301,45 +746,252 @@ impl ExpressionVisitor<Value> for Interpreter {
                             }
                             new_matrix.data = new_matrix2;
                         }
-                        Value::new_matrix(new_matrix.data, new_matrix.rows, new_matrix.cols)
-                    }
-                    ValueType::MATRIX(_m2) => {
-                        self.runtime_error("Cannot raise a matrix to a matrix");
-                        Value::new_scalar(0.0)
+                        Ok(Value::new_matrix(
+                            new_matrix.data,
+                            new_matrix.rows,
+                            new_matrix.cols,
+                        ))
                     }
+                    ValueType::MATRIX(_m2) => Err(Diagnostic::runtime(
+                        &binary.operator,
+                        "Cannot raise a matrix to a matrix",
+                    )),
+                    _ => unreachable!(),
                 },
+                _ => unreachable!(),
             },
-            _ => {
-                self.runtime_error("Unknown operator");
-                Value::new_scalar(0.0)
-            }
+            _ => Err(Diagnostic::runtime(&binary.operator, "Unknown operator")),
+        }
+    }
+
+    fn visit_logical(&mut self, logical: &Logical) -> Result<Value, Diagnostic> {
+        let operator = &logical.operator;
+        let left = logical.left.visit(self)?;
+        let left = self.is_true(operator, &left)?;
+        // and stops at the first false, or at the first true
+        if left == (operator.token_type == TokenType::TOKEN_OR) {
+            return Ok(Value::new_bool(left));
+        }
+        let right = logical.right.visit(self)?;
+        Ok(Value::new_bool(self.is_true(operator, &right)?))
+    }
+
+    fn visit_call(&mut self, call: &Call) -> Result<Value, Diagnostic> {
+        let callee = call.callee.visit(self)?;
+        if !matches!(callee.data, ValueType::FUNCTION(_) | ValueType::BUILTIN(_)) {
+            return Err(Diagnostic::runtime(&call.paren, "Can only call functions."));
         }
+        let arguments = call
+            .arguments
+            .iter()
+            .map(|argument| argument.visit(self))
+            .collect::<Result<Vec<Value>, Diagnostic>>()?;
+        // builtins report errors at their name, like the operators they used to be
+        let token = match (&callee.data, call.callee.as_ref()) {
+            (ValueType::BUILTIN(_), Expression::Identifier(identifier)) => &identifier.name,
+            _ => &call.paren,
+        };
+        self.call_value(&callee, arguments, token)
+    }
+
+    fn visit_lambda(&mut self, lambda: &Lambda) -> Result<Value, Diagnostic> {
+        Ok(Value::wrap_function(Function {
+            name: Token::new(
+                TokenType::TOKEN_IDENTIFIER,
+                "lambda".to_string(),
+                lambda.arrow.span,
+            ),
+            params: lambda.params.clone(),
+            body: Rc::clone(&lambda.body),
+            closure: Rc::clone(&self.environment),
+        }))
     }
 
-    fn visit_grouping(&mut self, grouping: &Grouping) -> Value {
+    fn visit_grouping(&mut self, grouping: &Grouping) -> Result<Value, Diagnostic> {
         grouping.expression.visit(self)
     }
 }
 
-impl StatementVisitor for Interpreter {
-    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
-        statement.expression.visit(self);
+impl StatementVisitor<Result<(), Diagnostic>> for Interpreter {
+    fn visit_expression_statement(
+        &mut self,
+        statement: &ExpressionStatement,
+    ) -> Result<(), Diagnostic> {
+        statement.expression.visit(self)?;
+        Ok(())
+    }
+
+    fn visit_print_statement(&mut self, statement: &PrintStatement) -> Result<(), Diagnostic> {
+        let values = statement
+            .expressions
+            .iter()
+            .map(|expression| expression.visit(self))
+            .collect::<Result<Vec<Value>, Diagnostic>>()?;
+        // a first string is a format string, values without a {} of their
+        // own follow it separated by spaces
+        let (mut pieces, values) = match &values[0].data {
+            ValueType::STRING(text) => {
+                let pieces = format::parse(text)
+                    .map_err(|message| Diagnostic::format(&statement.keyword, &message))?;
+                (pieces, &values[1..])
+            }
+            _ => (Vec::new(), &values[..]),
+        };
+        let placeholders = format::placeholders(&pieces);
+        if placeholders == 0 {
+            for _ in values {
+                if !pieces.is_empty() {
+                    pieces.push(format::Piece::Text(" ".to_string()));
+                }
+                pieces.push(format::Piece::Value(format::Spec::default()));
+            }
+        } else if placeholders != values.len() {
+            return Err(Diagnostic::format(
+                &statement.keyword,
+                &format!(
+                    "The format string needs {} values but got {}.",
+                    placeholders,
+                    values.len()
+                ),
+            ));
+        }
+        let output = format::render(&pieces, values, &self.settings);
+        self.write_output(&statement.keyword, &output)
     }
 
-    fn visit_print_statement(&mut self, statement: &PrintStatement) {
+    fn visit_let_statement(&mut self, statement: &LetStatement) -> Result<(), Diagnostic> {
+        let value = statement.initializer.visit(self)?;
+        if statement.names.len() == 1 {
+            self.declare(&statement.names[0], value, statement.constant)?;
+        } else {
+            match value.data {
+                ValueType::TUPLE(values) if values.len() == statement.names.len() => {
+                    for (name, value) in statement.names.iter().zip(values) {
+                        self.declare(name, value, statement.constant)?;
+                    }
+                }
+                _ => {
+                    return Err(Diagnostic::runtime(
+                        &statement.names[0],
+                        "Number of names does not match the number of values.",
+                    ))
+                }
+            }
+        }
+        if let Some(doc) = &statement.doc {
+            for name in &statement.names {
+                self.environment
+                    .borrow_mut()
+                    .document(name.lexeme.to_string(), doc.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_assign_statement(&mut self, statement: &AssignStatement) -> Result<(), Diagnostic> {
+        let value = statement.value.visit(self)?;
+        self.environment
+            .borrow_mut()
+            .assign(&statement.name.lexeme, value)
+            .map_err(|message| Diagnostic::runtime(&statement.name, &message))
+    }
+
+    fn visit_explain_statement(&mut self, statement: &ExplainStatement) -> Result<(), Diagnostic> {
+        let explain = self.explain;
+        self.explain = true;
         let value = statement.expression.visit(self);
-        value.print();
+        self.explain = explain;
+        let text = value?.to_text(&self.settings);
+        self.write_output(&statement.keyword, &text)
+    }
+
+    fn visit_set_statement(&mut self, statement: &SetStatement) -> Result<(), Diagnostic> {
+        let value = statement.value.visit(self)?;
+        let result = match value.data {
+            ValueType::SCALAR(s) => self.settings.set(&statement.name.lexeme, s.data),
+            _ => Err(format!(
+                "Setting {} must be a scalar",
+                statement.name.lexeme
+            )),
+        };
+        result.map_err(|message| Diagnostic::runtime(&statement.name, &message))
+    }
+
+    fn visit_function_statement(
+        &mut self,
+        statement: &FunctionStatement,
+    ) -> Result<(), Diagnostic> {
+        let function = Function {
+            name: statement.name.clone(),
+            params: statement.params.clone(),
+            body: Rc::clone(&statement.body),
+            closure: Rc::clone(&self.environment),
+        };
+        self.declare(&statement.name, Value::wrap_function(function), false)?;
+        if let Some(doc) = &statement.doc {
+            self.environment
+                .borrow_mut()
+                .document(statement.name.lexeme.to_string(), doc.clone());
+        }
+        Ok(())
+    }
+
+    fn visit_return_statement(&mut self, statement: &ReturnStatement) -> Result<(), Diagnostic> {
+        let value = statement.value.visit(self)?;
+        self.returning = Some(value);
+        Ok(())
+    }
+
+    fn visit_if_statement(&mut self, statement: &IfStatement) -> Result<(), Diagnostic> {
+        let condition = statement.condition.visit(self)?;
+        if self.is_true(&statement.keyword, &condition)? {
+            self.execute_block(&statement.then_branch, self.nested_environment())
+        } else {
+            self.execute_block(&statement.else_branch, self.nested_environment())
+        }
     }
 
-    fn visit_let_statement(&mut self, statement: &LetStatement) {
-        let value = statement.initializer.visit(self);
-        self.globals
-            .define(statement.name.lexeme.to_string(), value);
+    fn visit_while_statement(&mut self, statement: &WhileStatement) -> Result<(), Diagnostic> {
+        while self.returning.is_none() {
+            let condition = statement.condition.visit(self)?;
+            if !self.is_true(&statement.keyword, &condition)? {
+                break;
+            }
+            self.execute_block(&statement.body, self.nested_environment())?;
+        }
+        Ok(())
+    }
+
+    fn visit_for_statement(&mut self, statement: &ForStatement) -> Result<(), Diagnostic> {
+        let start = statement.start.visit(self)?;
+        let end = statement.end.visit(self)?;
+        let (start, end) = match (start.data, end.data) {
+            (ValueType::SCALAR(start), ValueType::SCALAR(end)) => (start.data, end.data),
+            _ => {
+                return Err(Diagnostic::runtime(
+                    &statement.range,
+                    "Range bounds must be scalars.",
+                ))
+            }
+        };
+        // count from the start each time so fractional bounds do not drift
+        let mut step = 0.0;
+        while start + step <= end && self.returning.is_none() {
+            let mut environment = self.nested_environment();
+            environment.define(
+                statement.variable.lexeme.to_string(),
+                Value::new_scalar(start + step),
+            );
+            self.execute_block(&statement.body, environment)?;
+            step += 1.0;
+        }
+        Ok(())
     }
 
-    fn visit_new_line_statement(&mut self, statement: &NewLineStatement) {
+    fn visit_new_line_statement(&mut self, statement: &NewLineStatement) -> Result<(), Diagnostic> {
         for _ in 0..statement.lines {
-            println!();
+            self.write_output(&statement.keyword, "")?;
         }
+        Ok(())
     }
 }
@@ -5,8 +5,10 @@
  * Date: 2022-11-07
  */
 use std::fs;
-use std::process::exit;
 
+use unicode_xid::UnicodeXID;
+
+use crate::tokens::Span;
 use crate::tokens::Token;
 use crate::tokens::TokenType;
 
@@ -14,55 +16,201 @@ pub struct Lexer {
     pub content: String,
 }
 
+/// The value of a number literal the lexer accepted.
+pub fn parse_number(lexeme: &str) -> f64 {
+    let digits = lexeme.replace('_', "");
+    let integer = |digits: &str, radix: u32| {
+        digits.chars().fold(0.0, |value, c| {
+            value * radix as f64 + c.to_digit(radix).unwrap() as f64
+        })
+    };
+    if let Some((numerator, denominator)) = digits
+        .strip_suffix('r')
+        .and_then(|fraction| fraction.split_once('/'))
+    {
+        return integer(numerator, 10) / integer(denominator, 10);
+    }
+    match digits.get(..2) {
+        Some("0x" | "0X") => integer(&digits[2..], 16),
+        Some("0b" | "0B") => integer(&digits[2..], 2),
+        _ => digits.parse().unwrap(),
+    }
+}
+
 impl Lexer {
-    fn number(&self, start: usize, line: usize, tokens: &mut Vec<Token>) -> usize {
-        let mut i: usize = start;
-        while i < self.content.len() && self.content.chars().nth(i).unwrap().is_numeric() {
-            i += 1;
-            if i < self.content.len() && self.content.chars().nth(i).unwrap() == '.' {
+    /// A number literal: decimals with an optional exponent, `0x` hex and
+    /// `0b` binary integers, or a fraction of whole numbers like `1/3r`.
+    /// Digits can be grouped with `_`.
+    fn number(source: &[char], start: usize, span: Span, tokens: &mut Vec<Token>) -> usize {
+        let at = |i: usize| source.get(i).copied();
+        let digits = |mut i: usize, radix: u32| {
+            while at(i).is_some_and(|c| c.is_digit(radix) || c == '_') {
                 i += 1;
-                while i < self.content.len() && self.content.chars().nth(i).unwrap().is_numeric() {
+            }
+            i
+        };
+        let suffix = |i: usize| at(i) == Some('r') && !at(i + 1).is_some_and(char::is_alphanumeric);
+        let radix = match (at(start), at(start + 1)) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        let mut i;
+        let mut denominator = None;
+        if radix == 10 {
+            i = digits(start, 10);
+            let end = digits(i + 1, 10);
+            // 1/3r is one literal, not 1 divided by 3r
+            if at(i) == Some('/') && at(i + 1).is_some_and(|c| c.is_ascii_digit()) && suffix(end) {
+                denominator = Some(i + 1..end);
+                i = end;
+            }
+            // 1..n is a range, not the number 1. followed by .n
+            if denominator.is_none() && at(i) == Some('.') && at(i + 1) != Some('.') {
+                i = digits(i + 1, 10);
+            }
+            if denominator.is_none() && matches!(at(i), Some('e' | 'E')) {
+                let sign = matches!(at(i + 1), Some('+' | '-'));
+                let exponent = if sign { i + 2 } else { i + 1 };
+                if at(exponent).is_some_and(|c| c.is_ascii_digit()) {
+                    i = digits(exponent, 10);
+                }
+            }
+        } else {
+            i = digits(start + 2, radix);
+        }
+        let message = if suffix(i) {
+            i += 1;
+            match denominator {
+                Some(range) if source[range.clone()].iter().all(|&c| c == '0' || c == '_') => {
+                    Some("A fraction literal cannot have a zero denominator")
+                }
+                Some(_) => None,
+                None => Some("Fraction literals are a whole number over a whole number, like 1/3r"),
+            }
+        } else if radix != 10 && i == start + 2 {
+            Some("Expected digits after the number prefix")
+        } else if radix != 10 && at(i).is_some_and(|c| c.is_ascii_alphanumeric()) {
+            Some("Invalid digit in number")
+        } else {
+            None
+        };
+        let span = Span { end: i, ..span };
+        match message {
+            Some(message) => tokens.push(Token::new(
+                TokenType::TOKEN_ERROR,
+                message.to_string(),
+                span,
+            )),
+            None => tokens.push(Token::new(
+                TokenType::TOKEN_NUMBER,
+                source[start..i].iter().collect(),
+                span,
+            )),
+        }
+        i
+    }
+
+    /// A string in double quotes, the token holds the text with escapes
+    /// replaced. Strings end at the end of a line if they are not closed.
+    fn string(source: &[char], start: usize, span: Span, tokens: &mut Vec<Token>) -> usize {
+        let mut i: usize = start + 1;
+        let mut text = String::new();
+        loop {
+            match source.get(i).copied() {
+                Some('"') => break,
+                None | Some('\n') => {
+                    tokens.push(Token::new(
+                        TokenType::TOKEN_ERROR,
+                        "Unterminated string".to_string(),
+                        Span { end: i, ..span },
+                    ));
+                    return i;
+                }
+                Some('\\') => {
                     i += 1;
+                    match source.get(i).copied() {
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        Some('"') => text.push('"'),
+                        Some('\\') => text.push('\\'),
+                        escape => {
+                            let escape = escape.map(String::from).unwrap_or_default();
+                            tokens.push(Token::new(
+                                TokenType::TOKEN_ERROR,
+                                format!("Unknown escape \\{}", escape),
+                                Span::new(
+                                    span.start + i - start - 1,
+                                    span.start + i - start + 1,
+                                    span.line,
+                                    span.column + i - start - 1,
+                                ),
+                            ));
+                            return i;
+                        }
+                    }
                 }
-                break;
+                Some(c) => text.push(c),
             }
+            i += 1;
         }
         tokens.push(Token::new(
-            TokenType::TOKEN_NUMBER,
-            self.content[start..i].to_string(),
-            line,
+            TokenType::TOKEN_STRING,
+            text,
+            Span { end: i + 1, ..span },
         ));
+        i + 1
+    }
+
+    /// Index of the newline ending the line `start` is on, or the end of the source.
+    fn line_end(source: &[char], start: usize) -> usize {
+        let mut i = start;
+        while let Some(c) = source.get(i).copied() {
+            if c == '\n' {
+                break;
+            }
+            i += 1;
+        }
         i
     }
 
-    fn identifier(&self, start: usize, line: usize, tokens: &mut Vec<Token>) -> usize {
-        let mut i: usize = start;
-        while i < self.content.len()
-            && (self.content.chars().nth(i).unwrap().is_alphabetic()
-                || self.content.chars().nth(i).unwrap().is_numeric())
-        {
+    /// Identifiers follow the Unicode XID rules, so `x_1`, `A_inv` and `θ` are
+    /// all names. Keywords only match whole words, `reference` is not `ref`.
+    fn identifier(source: &[char], start: usize, span: Span, tokens: &mut Vec<Token>) -> usize {
+        let mut i: usize = start + 1;
+        while source.get(i).copied().is_some_and(|c| c.is_xid_continue()) {
             i += 1;
         }
-        let lexeme: String = self.content[start..i].to_string();
+        let lexeme: String = source[start..i].iter().collect();
+        let span = Span { end: i, ..span };
         match lexeme.as_bytes() {
-            b"let" => tokens.push(Token::new(TokenType::TOKEN_LET, lexeme, line)),
-            b"print" => tokens.push(Token::new(TokenType::TOKEN_PRINT, lexeme, line)),
-            b"newline" => tokens.push(Token::new(TokenType::TOKEN_NEWLINE, lexeme, line)),
-            b"identity" => tokens.push(Token::new(TokenType::TOKEN_IDENTITY, lexeme, line)),
-            b"iden" => tokens.push(Token::new(TokenType::TOKEN_IDENTITY, lexeme, line)),
-            b"ref" => tokens.push(Token::new(TokenType::TOKEN_REF, lexeme, line)),
-            b"rref" => tokens.push(Token::new(TokenType::TOKEN_RREF, lexeme, line)),
-            b"inverse" => tokens.push(Token::new(TokenType::TOKEN_INVERSE, lexeme, line)),
-            b"inv" => tokens.push(Token::new(TokenType::TOKEN_INVERSE, lexeme, line)),
-            _ => tokens.push(Token::new(TokenType::TOKEN_IDENTIFIER, lexeme, line)),
+            b"let" => tokens.push(Token::new(TokenType::TOKEN_LET, lexeme, span)),
+            b"const" => tokens.push(Token::new(TokenType::TOKEN_CONST, lexeme, span)),
+            b"print" => tokens.push(Token::new(TokenType::TOKEN_PRINT, lexeme, span)),
+            b"newline" => tokens.push(Token::new(TokenType::TOKEN_NEWLINE, lexeme, span)),
+            b"explain" => tokens.push(Token::new(TokenType::TOKEN_EXPLAIN, lexeme, span)),
+            b"set" => tokens.push(Token::new(TokenType::TOKEN_SET, lexeme, span)),
+            b"fn" => tokens.push(Token::new(TokenType::TOKEN_FN, lexeme, span)),
+            b"return" => tokens.push(Token::new(TokenType::TOKEN_RETURN, lexeme, span)),
+            b"if" => tokens.push(Token::new(TokenType::TOKEN_IF, lexeme, span)),
+            b"else" => tokens.push(Token::new(TokenType::TOKEN_ELSE, lexeme, span)),
+            b"while" => tokens.push(Token::new(TokenType::TOKEN_WHILE, lexeme, span)),
+            b"for" => tokens.push(Token::new(TokenType::TOKEN_FOR, lexeme, span)),
+            b"in" => tokens.push(Token::new(TokenType::TOKEN_IN, lexeme, span)),
+            b"true" => tokens.push(Token::new(TokenType::TOKEN_TRUE, lexeme, span)),
+            b"false" => tokens.push(Token::new(TokenType::TOKEN_FALSE, lexeme, span)),
+            b"and" => tokens.push(Token::new(TokenType::TOKEN_AND, lexeme, span)),
+            b"or" => tokens.push(Token::new(TokenType::TOKEN_OR, lexeme, span)),
+            _ => tokens.push(Token::new(TokenType::TOKEN_IDENTIFIER, lexeme, span)),
         }
         i
     }
 
-    pub fn new(file_path: &str) -> Self {
-        Lexer {
-            content: read_file(file_path),
-        }
+    /// A lexer for the contents of the file at `file_path`.
+    pub fn new(file_path: &str) -> std::io::Result<Self> {
+        Ok(Lexer {
+            content: fs::read_to_string(file_path)?,
+        })
     }
 
     pub fn new_empty() -> Self {
@@ -74,130 +222,287 @@ impl Lexer {
     pub fn scan_tokens(&self) -> Vec<Token> {
         let mut tokens = Vec::new();
         let mut line: usize = 1;
+        let mut line_start: usize = 0;
         let mut index: usize = 0;
-        while index < self.content.len() {
-            let c = self.content.chars().nth(index).unwrap();
+        // indices count characters, collecting them once keeps lookups
+        // constant time and never splits a multi-byte character. Spans are
+        // turned into byte offsets at the end.
+        let source: Vec<char> = self.content.chars().collect();
+        while index < source.len() {
+            let c = source[index];
+            let span = Span::new(index, index + 1, line, index - line_start + 1);
             match c {
-                '0'..='9' => index = self.number(index, line, &mut tokens) - 1,
-                'a'..='z' | 'A'..='Z' => index = self.identifier(index, line, &mut tokens) - 1,
-                '\n' => line += 1,
+                '0'..='9' => index = Self::number(&source, index, span, &mut tokens) - 1,
+                '"' => index = Self::string(&source, index, span, &mut tokens) - 1,
+                c if c == '_' || c.is_xid_start() => {
+                    index = Self::identifier(&source, index, span, &mut tokens) - 1
+                }
+                '\n' => {
+                    line += 1;
+                    line_start = index + 1;
+                }
                 ' ' => (),
                 '\t' => (),
                 '\r' => (),
-                '+' => tokens.push(Token::new(TokenType::TOKEN_PLUS, c.to_string(), line)),
-                '-' => tokens.push(Token::new(TokenType::TOKEN_MINUS, c.to_string(), line)),
-                '*' => tokens.push(Token::new(TokenType::TOKEN_STAR, c.to_string(), line)),
-                '/' => tokens.push(Token::new(TokenType::TOKEN_SLASH, c.to_string(), line)),
+                '+' => {
+                    if source.get(index + 1) == Some(&'=') {
+                        tokens.push(Token::new(
+                            TokenType::TOKEN_PLUS_EQUAL,
+                            "+=".to_string(),
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
+                        ));
+                        index += 1;
+                    } else {
+                        tokens.push(Token::new(TokenType::TOKEN_PLUS, c.to_string(), span));
+                    }
+                }
+                '-' => {
+                    if source.get(index + 1) == Some(&'>') {
+                        tokens.push(Token::new(
+                            TokenType::TOKEN_ARROW,
+                            "->".to_string(),
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
+                        ));
+                        index += 1;
+                    } else if source.get(index + 1) == Some(&'=') {
+                        tokens.push(Token::new(
+                            TokenType::TOKEN_MINUS_EQUAL,
+                            "-=".to_string(),
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
+                        ));
+                        index += 1;
+                    } else {
+                        tokens.push(Token::new(TokenType::TOKEN_MINUS, c.to_string(), span));
+                    }
+                }
+                '*' => {
+                    if source.get(index + 1) == Some(&'=') {
+                        tokens.push(Token::new(
+                            TokenType::TOKEN_STAR_EQUAL,
+                            "*=".to_string(),
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
+                        ));
+                        index += 1;
+                    } else {
+                        tokens.push(Token::new(TokenType::TOKEN_STAR, c.to_string(), span));
+                    }
+                }
+                '#' => index = Self::line_end(&source, index) - 1,
+                '/' => match source.get(index + 1).copied() {
+                    Some('/') => {
+                        let end = Self::line_end(&source, index);
+                        // /// is a doc comment, //// and longer are not
+                        if source.get(index + 2) == Some(&'/')
+                            && source.get(index + 3) != Some(&'/')
+                        {
+                            let text: String = source[index + 3..end].iter().collect();
+                            let text = text.strip_prefix(' ').unwrap_or(&text).trim_end();
+                            tokens.push(Token::new(
+                                TokenType::TOKEN_DOC_COMMENT,
+                                text.to_string(),
+                                Span { end, ..span },
+                            ));
+                        }
+                        index = end - 1;
+                    }
+                    Some('*') => {
+                        // block comments nest, so code holding one can be commented out
+                        let mut depth = 0;
+                        let mut i = index;
+                        loop {
+                            match (source.get(i).copied(), source.get(i + 1).copied()) {
+                                (Some('/'), Some('*')) => {
+                                    depth += 1;
+                                    i += 2;
+                                }
+                                (Some('*'), Some('/')) => {
+                                    depth -= 1;
+                                    i += 2;
+                                    if depth == 0 {
+                                        break;
+                                    }
+                                }
+                                (Some('\n'), _) => {
+                                    line += 1;
+                                    line_start = i + 1;
+                                    i += 1;
+                                }
+                                (Some(_), _) => i += 1,
+                                (None, _) => {
+                                    tokens.push(Token::new(
+                                        TokenType::TOKEN_ERROR,
+                                        "Unterminated block comment".to_string(),
+                                        Span {
+                                            end: index + 2,
+                                            ..span
+                                        },
+                                    ));
+                                    break;
+                                }
+                            }
+                        }
+                        index = i - 1;
+                    }
+                    _ => tokens.push(Token::new(TokenType::TOKEN_SLASH, c.to_string(), span)),
+                },
                 '^' => {
-                    if index < self.content.len() - 1
-                        && self.content.chars().nth(index + 1).unwrap() == 'T'
-                    {
+                    if source.get(index + 1) == Some(&'T') {
                         tokens.push(Token::new(
                             TokenType::TOKEN_TRANSPOSE,
                             c.to_string() + &c.to_string(),
-                            line,
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
                         ));
                         index += 1;
                     } else {
-                        tokens.push(Token::new(TokenType::TOKEN_CARET, c.to_string(), line));
+                        tokens.push(Token::new(TokenType::TOKEN_CARET, c.to_string(), span));
                     }
                 }
                 '=' => {
-                    if self.content.chars().nth(index + 1).unwrap() == '=' {
+                    if source.get(index + 1) == Some(&'=') {
                         tokens.push(Token::new(
                             TokenType::TOKEN_EQUAL_EQUAL,
                             "==".to_string(),
-                            line,
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
                         ));
                         index += 1;
                     } else {
-                        tokens.push(Token::new(TokenType::TOKEN_EQUAL, c.to_string(), line));
+                        tokens.push(Token::new(TokenType::TOKEN_EQUAL, c.to_string(), span));
                     }
                 }
                 '!' => {
-                    if self.content.chars().nth(index + 1).unwrap() == '=' {
+                    if source.get(index + 1) == Some(&'=') {
                         tokens.push(Token::new(
                             TokenType::TOKEN_BANG_EQUAL,
                             "!=".to_string(),
-                            line,
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
                         ));
                         index += 1;
                     } else {
-                        tokens.push(Token::new(TokenType::TOKEN_BANG, c.to_string(), line));
+                        tokens.push(Token::new(TokenType::TOKEN_BANG, c.to_string(), span));
                     }
                 }
                 '<' => {
-                    if self.content.chars().nth(index + 1).unwrap() == '=' {
+                    if source.get(index + 1) == Some(&'=') {
                         tokens.push(Token::new(
                             TokenType::TOKEN_LESS_EQUAL,
                             "<=".to_string(),
-                            line,
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
                         ));
                         index += 1;
                     } else {
-                        tokens.push(Token::new(TokenType::TOKEN_LESS, c.to_string(), line));
+                        tokens.push(Token::new(TokenType::TOKEN_LESS, c.to_string(), span));
                     }
                 }
                 '>' => {
-                    if self.content.chars().nth(index + 1).unwrap() == '=' {
+                    if source.get(index + 1) == Some(&'=') {
                         tokens.push(Token::new(
                             TokenType::TOKEN_GREATER_EQUAL,
                             ">=".to_string(),
-                            line,
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
                         ));
                         index += 1;
                     } else {
-                        tokens.push(Token::new(TokenType::TOKEN_GREATER, c.to_string(), line));
+                        tokens.push(Token::new(TokenType::TOKEN_GREATER, c.to_string(), span));
                     }
                 }
-                '(' => tokens.push(Token::new(TokenType::TOKEN_LEFT_PAREN, c.to_string(), line)),
+                '(' => tokens.push(Token::new(TokenType::TOKEN_LEFT_PAREN, c.to_string(), span)),
                 ')' => tokens.push(Token::new(
                     TokenType::TOKEN_RIGHT_PAREN,
                     c.to_string(),
-                    line,
+                    span,
                 )),
-                '{' => tokens.push(Token::new(TokenType::TOKEN_LEFT_BRACE, c.to_string(), line)),
+                '{' => tokens.push(Token::new(TokenType::TOKEN_LEFT_BRACE, c.to_string(), span)),
                 '}' => tokens.push(Token::new(
                     TokenType::TOKEN_RIGHT_BRACE,
                     c.to_string(),
-                    line,
+                    span,
                 )),
                 '[' => tokens.push(Token::new(
                     TokenType::TOKEN_LEFT_BRACKET,
                     c.to_string(),
-                    line,
+                    span,
                 )),
                 ']' => tokens.push(Token::new(
                     TokenType::TOKEN_RIGHT_BRACKET,
                     c.to_string(),
-                    line,
+                    span,
                 )),
-                ',' => tokens.push(Token::new(TokenType::TOKEN_COMMA, c.to_string(), line)),
-                '.' => tokens.push(Token::new(TokenType::TOKEN_DOT, c.to_string(), line)),
-                ';' => tokens.push(Token::new(TokenType::TOKEN_SEMICOLON, c.to_string(), line)),
-                '|' => tokens.push(Token::new(TokenType::TOKEN_PIPE, c.to_string(), line)),
+                ',' => tokens.push(Token::new(TokenType::TOKEN_COMMA, c.to_string(), span)),
+                '.' => {
+                    if source.get(index + 1) == Some(&'.') {
+                        tokens.push(Token::new(
+                            TokenType::TOKEN_DOT_DOT,
+                            "..".to_string(),
+                            Span {
+                                end: index + 2,
+                                ..span
+                            },
+                        ));
+                        index += 1;
+                    } else if source
+                        .get(index + 1)
+                        .copied()
+                        .is_some_and(|c| c.is_ascii_digit())
+                    {
+                        index = Self::number(&source, index, span, &mut tokens) - 1;
+                    } else {
+                        tokens.push(Token::new(TokenType::TOKEN_DOT, c.to_string(), span));
+                    }
+                }
+                ';' => tokens.push(Token::new(TokenType::TOKEN_SEMICOLON, c.to_string(), span)),
+                '|' => tokens.push(Token::new(TokenType::TOKEN_PIPE, c.to_string(), span)),
                 _ => tokens.push(Token::new(
                     TokenType::TOKEN_ERROR,
                     "Unknown token: ".to_string() + &c.to_string(),
-                    line,
+                    span,
                 )),
             }
             index += 1;
         }
-        tokens.push(Token::new(TokenType::TOKEN_EOF, "".to_string(), line));
-        tokens
-    }
-}
-
-fn read_file(file_path: &str) -> String {
-    let data = fs::read_to_string(file_path);
-    match data {
-        Ok(data) => {
-            return data;
-        }
-        Err(error) => {
-            println!("Failed to read file: {}", error);
-            exit(1);
+        let end = source.len();
+        tokens.push(Token::new(
+            TokenType::TOKEN_EOF,
+            "".to_string(),
+            Span::new(end, end, line, end - line_start + 1),
+        ));
+        // the byte offset of every character, and of the end of the source
+        let offsets: Vec<usize> = self
+            .content
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(self.content.len()))
+            .collect();
+        for token in &mut tokens {
+            token.span.start = offsets[token.span.start];
+            token.span.end = offsets[token.span.end];
         }
+        tokens
     }
 }
@@ -0,0 +1,35 @@
+//! A small language for linear algebra. The `linalg` executable runs scripts
+//! and the REPL, programs can embed the same interpreter:
+//!
+//! ```
+//! use linear_algebra_ast::interpreter::Interpreter;
+//! use linear_algebra_ast::value::Value;
+//!
+//! let mut interpreter = Interpreter::new();
+//! let a = Value::try_from(vec![vec![2.0, 0.0], vec![0.0, 4.0]]).unwrap();
+//! interpreter.set("A", a).unwrap();
+//! let inverse = interpreter.eval_str("inv(A)").unwrap();
+//! let rows: Vec<Vec<f64>> = inverse.try_into().unwrap();
+//! assert_eq!(rows, vec![vec![0.5, 0.0], vec![0.0, 0.25]]);
+//! ```
+
+pub mod analyzer;
+pub mod builtins;
+pub mod environment;
+pub mod error;
+mod format;
+pub mod function;
+pub mod interpreter;
+pub mod lexer;
+pub mod native;
+pub mod output;
+pub mod parser;
+pub mod polynomial;
+pub mod settings;
+pub mod tokens;
+pub mod value;
+pub mod ast {
+    pub mod astprinter;
+    pub mod expression;
+    pub mod statement;
+}
@@ -1,82 +1,251 @@
-mod interpreter;
-mod lexer;
-mod parser;
-mod tokens;
-mod value;
-mod environment;
-mod ast {
-    pub mod astprinter;
-    pub mod expression;
-    pub mod statement;
-}
-
 use std::io::Write;
 
-use crate::interpreter::Interpreter;
-use crate::lexer::Lexer;
-use crate::parser::Parser;
+use linear_algebra_ast::analyzer::Analyzer;
+use linear_algebra_ast::ast::statement::Statement;
+use linear_algebra_ast::error::Diagnostic;
+use linear_algebra_ast::interpreter::Interpreter;
+use linear_algebra_ast::lexer::Lexer;
+use linear_algebra_ast::parser::Parser;
+use linear_algebra_ast::settings::Settings;
+use linear_algebra_ast::tokens::TokenType;
+use linear_algebra_ast::value::Value;
+use linear_algebra_ast::value::ValueType;
 
 fn main() {
     let args = std::env::args().collect::<Vec<String>>();
 
-    if args.len() == 0 {
-        println!("Usage: linalg <file>");
-        std::process::exit(1);
-    } else if args.len() == 1 {
-        repl();
-        return;
+    let mut settings = Settings::new();
+    let mut explain = false;
+    let mut warn_shadow = false;
+    let mut file: Option<&String> = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--explain" => explain = true,
+            "--warn-shadow" => warn_shadow = true,
+            "--tolerance" | "--snap" | "--precision" => {
+                let value = args.next().and_then(|value| value.parse::<f64>().ok());
+                let result = match value {
+                    Some(value) => settings.set(&arg[2..], value),
+                    None => Err(format!("Expected a number after {}", arg)),
+                };
+                if let Err(error) = result {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+            _ if arg.starts_with("--") || file.is_some() => {
+                eprintln!(
+                    "Usage: linalg [--explain] [--warn-shadow] [--tolerance n] [--snap n] [--precision n] [file]"
+                );
+                std::process::exit(1);
+            }
+            _ => file = Some(arg),
+        }
     }
 
-    let file = &args[1];
+    let mut interpreter = Interpreter::with_settings(settings);
+    interpreter.explain = explain;
+    interpreter.warn_shadowing = warn_shadow;
+
+    let file = match file {
+        Some(file) => file,
+        None => {
+            repl(interpreter);
+            return;
+        }
+    };
 
-    let lexer = Lexer::new(file);
+    let lexer = match Lexer::new(file) {
+        Ok(lexer) => lexer,
+        Err(error) => {
+            eprintln!("Failed to read file: {}", error);
+            std::process::exit(1);
+        }
+    };
     let tokens = lexer.scan_tokens();
     for token in &tokens {
-        println!("{}", token);
+        written(writeln!(interpreter.output, "{}", token));
     }
 
     let mut parser: Parser = Parser::new(tokens);
     let statements = parser.parse();
 
-    let mut interpreter = Interpreter::new();
-
     match statements {
         Ok(statements) => {
-            interpreter.interpret(statements);
+            if let Err(errors) = analyze(&mut interpreter, &statements, &lexer.content) {
+                for error in errors {
+                    report(&mut interpreter, &error.report(&lexer.content));
+                }
+                std::process::exit(1);
+            }
+            if let Err(error) = interpreter.interpret(statements) {
+                report(&mut interpreter, &error.report(&lexer.content));
+                std::process::exit(1);
+            }
         }
-        Err(error) => {
-            println!("{}", error);
+        Err(errors) => {
+            // nothing runs until the whole program parses
+            for error in errors {
+                report(&mut interpreter, &error.report(&lexer.content));
+            }
+            std::process::exit(1);
         }
     }
 }
 
-fn repl() {
+/// Checks shapes against the variables defined so far, reporting any warnings.
+fn analyze(
+    interpreter: &mut Interpreter,
+    statements: &[Statement],
+    source: &str,
+) -> Result<(), Vec<Diagnostic>> {
+    let (result, warnings) = {
+        let globals = interpreter.globals.borrow();
+        let mut analyzer = Analyzer::new(&globals, &interpreter.builtins)
+            .warn_shadowing(interpreter.warn_shadowing);
+        (analyzer.analyze(statements), analyzer.warnings)
+    };
+    for warning in warnings {
+        report(interpreter, &warning.report(source));
+    }
+    result
+}
+
+fn repl(mut interpreter: Interpreter) {
     let mut lexer = Lexer::new_empty();
     let mut parser = Parser::new_empty();
-    let mut interpreter = Interpreter::new();
 
     loop {
-        print!("LA > ");
+        prompt(&mut interpreter, "LA > ");
         let mut input = String::new();
-        match std::io::stdout().flush() {
-            Ok(_) => {},
-            Err(error) => {
-                println!("Error flushing stdout: {}", error);
-                std::process::exit(1);
+        // the end of the input ends the session
+        if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+            return;
+        }
+        while needs_more(&input) {
+            prompt(&mut interpreter, "...  ");
+            if std::io::stdin().read_line(&mut input).unwrap() == 0 {
+                break;
             }
         }
-        std::io::stdin().read_line(&mut input).unwrap();
+        if let Some(name) = help_request(&input) {
+            written(help(&mut interpreter, name));
+            continue;
+        }
         lexer.content = input;
         let tokens = lexer.scan_tokens();
         parser.set_tokens(tokens);
         let statements = parser.parse();
         match statements {
             Ok(statements) => {
-                interpreter.interpret(statements);
+                if let Err(errors) = analyze(&mut interpreter, &statements, &lexer.content) {
+                    for error in errors {
+                        report(&mut interpreter, &error.report(&lexer.content));
+                    }
+                    continue;
+                }
+                // the session keeps going, variables defined so far stay in scope
+                if let Err(error) = interpreter.interpret(statements) {
+                    report(&mut interpreter, &error.report(&lexer.content));
+                }
             }
-            Err(error) => {
-                println!("{}", error);
+            Err(errors) => {
+                for error in errors {
+                    report(&mut interpreter, &error.report(&lexer.content));
+                }
             }
         }
     }
 }
+
+/// Whether the REPL should keep reading: a block is still open, or the input
+/// ends with a doc comment for the declaration that follows. Braces in
+/// strings and comments do not count.
+fn needs_more(input: &str) -> bool {
+    let mut lexer = Lexer::new_empty();
+    lexer.content = input.to_string();
+    let tokens = lexer.scan_tokens();
+    let depth = tokens
+        .iter()
+        .fold(0i64, |depth, token| match token.token_type {
+            TokenType::TOKEN_LEFT_BRACE => depth + 1,
+            TokenType::TOKEN_RIGHT_BRACE => depth - 1,
+            _ => depth,
+        });
+    depth > 0
+        || tokens
+            .iter()
+            .rev()
+            .find(|token| token.token_type != TokenType::TOKEN_EOF)
+            .is_some_and(|token| token.token_type == TokenType::TOKEN_DOC_COMMENT)
+}
+
+/// The name after `help`, empty for a bare `help`. Anything else is code.
+fn help_request(input: &str) -> Option<&str> {
+    let rest = input.trim().strip_prefix("help")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = rest.trim();
+    name.chars()
+        .all(|c| c.is_alphanumeric() || c == '_')
+        .then_some(name)
+}
+
+/// Shows the /// comments of a global, or lists every documented global.
+fn help(interpreter: &mut Interpreter, name: &str) -> std::io::Result<()> {
+    let globals = interpreter.globals.borrow();
+    let output = &mut interpreter.output;
+    if name.is_empty() {
+        let docs = globals.documented();
+        if docs.is_empty() {
+            writeln!(
+                output,
+                "Nothing is documented yet, write /// comments above a let, const or fn"
+            )?;
+        }
+        for (name, doc) in docs {
+            writeln!(output, "{}: {}", name, doc.lines().next().unwrap_or(""))?;
+        }
+        return Ok(());
+    }
+    let value = globals
+        .lookup(&name.to_string())
+        .or_else(|| interpreter.builtins.get(name).map(Value::wrap_builtin));
+    let Some(value) = value else {
+        return writeln!(output, "Undefined variable {}", name);
+    };
+    match &value.data {
+        ValueType::FUNCTION(function) => {
+            let params: Vec<&str> = function.params.iter().map(|p| p.lexeme.as_str()).collect();
+            writeln!(output, "fn {}({})", name, params.join(", "))?;
+        }
+        ValueType::BUILTIN(builtin) => {
+            return writeln!(output, "builtin {}", builtin.usage);
+        }
+        _ => (),
+    }
+    match globals.doc(name) {
+        Some(doc) => writeln!(output, "{}", doc),
+        None => writeln!(output, "{} has no doc comment", name),
+    }
+}
+
+fn prompt(interpreter: &mut Interpreter, text: &str) {
+    written(write!(interpreter.output, "{}", text).and_then(|_| interpreter.output.flush()));
+}
+
+/// Errors go to the diagnostics sink, stderr unless replaced.
+fn report(interpreter: &mut Interpreter, text: &str) {
+    written(interpreter.diagnose(text));
+}
+
+/// Stops the tool when its output cannot be written, there is nowhere left
+/// to show anything.
+fn written(result: std::io::Result<()>) {
+    if let Err(error) = result {
+        let _ = writeln!(std::io::stderr(), "Could not write the output: {}", error);
+        std::process::exit(1);
+    }
+}
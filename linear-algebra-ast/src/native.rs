@@ -0,0 +1,153 @@
+use std::ops::RangeInclusive;
+
+use crate::analyzer::Shape;
+use crate::value::Matrix;
+use crate::value::Value;
+use crate::value::ValueType;
+
+/// A function written in Rust by a program embedding the interpreter, called
+/// from scripts like any builtin. Closures taking `&Arguments` implement it,
+/// so most only need `interpreter.register("name", |args| ...)`.
+pub trait NativeFunction {
+    /// Runs the function. An error message is reported at the call in the
+    /// script, like any other runtime error.
+    fn call(&self, args: &Arguments) -> Result<Value, String>;
+
+    /// How many arguments it takes, calls with any other number are errors
+    /// before `call` runs.
+    fn arity(&self) -> RangeInclusive<usize> {
+        0..=usize::MAX
+    }
+
+    /// How to call it, shown by help and when the number of arguments is wrong.
+    fn usage(&self, name: &str) -> String {
+        format!("{}(...)", name)
+    }
+}
+
+impl<F> NativeFunction for F
+where
+    F: Fn(&Arguments) -> Result<Value, String>,
+{
+    fn call(&self, args: &Arguments) -> Result<Value, String> {
+        self(args)
+    }
+}
+
+/// The arguments a native function was called with.
+pub struct Arguments<'a> {
+    /// the name it was called by, for error messages
+    name: &'a str,
+    values: Vec<Value>,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn new(name: &'a str, values: Vec<Value>) -> Self {
+        Arguments { name, values }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// The argument at `index` as a Rust type, e.g. `let id: f64 = args.get(0)?;`.
+    /// The error says which argument was wrong and what it should have been.
+    pub fn get<T: FromValue>(&self, index: usize) -> Result<T, String> {
+        let Some(value) = self.values.get(index) else {
+            return Err(format!(
+                "{} needs at least {} argument{}",
+                self.name,
+                index + 1,
+                if index == 0 { "" } else { "s" }
+            ));
+        };
+        T::from_value(value).ok_or_else(|| {
+            format!(
+                "Argument {} of {} must be {}, not {}",
+                index + 1,
+                self.name,
+                T::EXPECTED,
+                Shape::of(value)
+            )
+        })
+    }
+}
+
+/// Rust types script values can be converted to.
+pub trait FromValue: Sized {
+    /// what the value has to be, e.g. "a scalar"
+    const EXPECTED: &'static str;
+
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FromValue for f64 {
+    const EXPECTED: &'static str = "a scalar";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match &value.data {
+            ValueType::SCALAR(s) => Some(s.data),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for usize {
+    const EXPECTED: &'static str = "a whole number";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match &value.data {
+            ValueType::SCALAR(s) if s.data >= 0.0 && s.data.fract() == 0.0 => Some(s.data as usize),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match &value.data {
+            ValueType::BOOL(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match &value.data {
+            ValueType::STRING(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Matrix {
+    const EXPECTED: &'static str = "a matrix";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match &value.data {
+            ValueType::MATRIX(m) => Some(m.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Value {
+    const EXPECTED: &'static str = "a value";
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
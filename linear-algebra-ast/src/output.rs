@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+/// An in-memory sink for the interpreter's output or diagnostics. Clones
+/// share the same buffer, so keep one to read what was written to the other:
+///
+/// ```
+/// use linear_algebra_ast::interpreter::Interpreter;
+/// use linear_algebra_ast::output::Capture;
+///
+/// let output = Capture::new();
+/// let mut interpreter = Interpreter::new();
+/// interpreter.output = Box::new(output.clone());
+/// interpreter.eval_str("print 1 + 2;").unwrap();
+/// assert_eq!(output.contents(), "3\n");
+/// ```
+#[derive(Clone, Default)]
+pub struct Capture {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture::default()
+    }
+
+    /// Everything written so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.buffer.borrow_mut().clear();
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,31 +1,71 @@
 use crate::ast::expression::Binary;
+use crate::ast::expression::Call;
 use crate::ast::expression::Expression;
 use crate::ast::expression::Grouping;
 use crate::ast::expression::Identifier;
+use crate::ast::expression::Lambda;
 use crate::ast::expression::Literal;
+use crate::ast::expression::Logical;
 use crate::ast::expression::Unary;
+use crate::ast::statement::AssignStatement;
+use crate::ast::statement::ExplainStatement;
 use crate::ast::statement::ExpressionStatement;
+use crate::ast::statement::ForStatement;
+use crate::ast::statement::FunctionStatement;
+use crate::ast::statement::IfStatement;
 use crate::ast::statement::LetStatement;
 use crate::ast::statement::NewLineStatement;
 use crate::ast::statement::PrintStatement;
+use crate::ast::statement::ReturnStatement;
+use crate::ast::statement::SetStatement;
 use crate::ast::statement::Statement;
+use crate::ast::statement::WhileStatement;
+use crate::error::Diagnostic;
+use crate::lexer::parse_number;
 use crate::tokens::Token;
 use crate::tokens::TokenType;
-use crate::value::Matrix;
 use crate::value::Value;
+use crate::value::ValueType;
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     error: bool,
+    errors: Vec<Diagnostic>,
+    /// how many function bodies deep the parser is, return is only allowed inside one
+    function_depth: usize,
+    /// whether the last statement may be an expression without a `;`
+    trailing_expression: bool,
+}
+
+/// Drops /// comments that are not directly above a let, const or fn, so a
+/// banner or a note above other code is an ordinary comment.
+fn without_stray_docs(mut tokens: Vec<Token>) -> Vec<Token> {
+    let mut keep = vec![true; tokens.len()];
+    let mut documented = false;
+    for (i, token) in tokens.iter().enumerate().rev() {
+        match token.token_type {
+            TokenType::TOKEN_DOC_COMMENT => keep[i] = documented,
+            TokenType::TOKEN_LET | TokenType::TOKEN_CONST | TokenType::TOKEN_FN => {
+                documented = true
+            }
+            _ => documented = false,
+        }
+    }
+    let mut keep = keep.into_iter();
+    tokens.retain(|_| keep.next().unwrap_or(true));
+    tokens
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Parser {
-            tokens,
+            tokens: without_stray_docs(tokens),
             current: 0,
             error: false,
+            errors: Vec::new(),
+            function_depth: 0,
+            trailing_expression: false,
         }
     }
 
@@ -34,19 +74,44 @@ impl Parser {
             tokens: Vec::new(),
             current: 0,
             error: false,
+            errors: Vec::new(),
+            function_depth: 0,
+            trailing_expression: false,
         }
     }
 
+    /// Lets the program end with an expression and no `;`, like `inv(A)`,
+    /// for evaluating a snippet for its value.
+    pub fn trailing_expression(mut self, trailing_expression: bool) -> Self {
+        self.trailing_expression = trailing_expression;
+        self
+    }
+
     pub fn set_tokens(&mut self, tokens: Vec<Token>) {
-        self.tokens = tokens;
+        self.tokens = without_stray_docs(tokens);
         self.current = 0;
+        self.error = false;
+        self.errors.clear();
+        self.function_depth = 0;
     }
 
     pub fn parse_error(&mut self, content: &str) {
-        let token = self.tokens.get(self.current).unwrap();
-        let line = token.line;
+        let token = self.tokens[self.current].clone();
+        if token.token_type == TokenType::TOKEN_ERROR {
+            // the lexer already knows what is wrong here
+            self.error_at(&token, &token.lexeme);
+        } else {
+            self.error_at(&token, content);
+        }
+    }
+
+    fn error_at(&mut self, token: &Token, content: &str) {
+        // only the first error of a statement is recorded, the rest tend to
+        // be knock-on effects of it
+        if !self.error {
+            self.errors.push(Diagnostic::parse(token, content));
+        }
         self.error = true;
-        println!("Error at line {}: {}", line, content);
     }
 
     fn previous(&mut self) -> &Token {
@@ -90,22 +155,22 @@ impl Parser {
             return true;
         }
         self.parse_error(message);
-        return false;
+        false
     }
 
     fn synchronize(&mut self) {
-        self.advance();
+        // the statement may have been read up to its ';' despite the error
+        if self.previous().token_type != TokenType::TOKEN_SEMICOLON {
+            self.advance();
+        }
         while !self.is_at_end() {
             if self.previous().token_type == TokenType::TOKEN_SEMICOLON {
                 self.error = false;
                 return;
             }
-            match self.peek().token_type {
-                TokenType::TOKEN_PRINT => {
-                    self.error = false;
-                    return;
-                }
-                _ => (),
+            if self.peek().token_type == TokenType::TOKEN_PRINT {
+                self.error = false;
+                return;
             }
             self.advance();
         }
@@ -115,30 +180,48 @@ impl Parser {
         let mut matrix: Vec<f64> = Vec::new();
         let mut rows: usize = 1;
         let mut cols: usize = 0;
+        // entries in the row being read
+        let mut row: usize = 0;
         while !self.check(TokenType::TOKEN_RIGHT_BRACKET) && !self.is_at_end() {
             if self.check(TokenType::TOKEN_PIPE) {
-                if cols == 0 {
-                    cols = matrix.len();
-                } else if matrix.len() % cols != 0 {
-                    self.parse_error("Invalid matrix dimensions");
-                }
+                self.end_matrix_row(row, &mut cols);
+                row = 0;
                 rows += 1;
                 self.advance();
                 continue;
             }
+            let negative = self.match_token(TokenType::TOKEN_MINUS);
             if self.consume(TokenType::TOKEN_NUMBER, "Expected a number") {
-                matrix.push(self.previous().lexeme.parse::<f64>().unwrap());
+                let number = parse_number(&self.previous().lexeme);
+                matrix.push(if negative { -number } else { number });
+                row += 1;
+            } else {
+                // skip the offending token so a bad entry can't stall the loop
+                self.advance();
             }
         }
-        if cols == 0 {
-            cols = matrix.len();
-        } else if matrix.len() % cols != 0 {
-            self.parse_error("Invalid matrix dimensions");
+        // [] is the empty matrix, any other empty row is a mistake
+        if rows > 1 || row > 0 {
+            self.end_matrix_row(row, &mut cols);
         }
-        if self.consume(TokenType::TOKEN_RIGHT_BRACKET, "Expected ']' after matrix") {
-            return Expression::Literal(Literal::new(Value::new_matrix(matrix, rows, cols)));
+        if self.consume(TokenType::TOKEN_RIGHT_BRACKET, "Expected ']' after matrix")
+            && matrix.len() == rows * cols
+        {
+            Expression::Literal(Literal::new(Value::new_matrix(matrix, rows, cols)))
         } else {
-            return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
+            Expression::Literal(Literal::new(Value::new_scalar(0.0)))
+        }
+    }
+
+    /// Checks a finished matrix row of `row` entries, the first one sets how
+    /// many columns every other row needs.
+    fn end_matrix_row(&mut self, row: usize, cols: &mut usize) {
+        if row == 0 {
+            self.parse_error("Expected a number, matrix rows cannot be empty");
+        } else if *cols == 0 {
+            *cols = row;
+        } else if row != *cols {
+            self.parse_error("Invalid matrix dimensions");
         }
     }
 
@@ -147,9 +230,12 @@ impl Parser {
         let token = self.previous();
         let value = match token.token_type {
             TokenType::TOKEN_NUMBER => {
-                let number: f64 = token.lexeme.parse::<f64>().unwrap();
+                let number = parse_number(&token.lexeme);
                 Value::new_scalar(number)
             }
+            TokenType::TOKEN_STRING => Value::new_string(token.lexeme.clone()),
+            TokenType::TOKEN_TRUE => Value::new_bool(true),
+            TokenType::TOKEN_FALSE => Value::new_bool(false),
             TokenType::TOKEN_LEFT_BRACKET => return self.matrix(),
             TokenType::TOKEN_LEFT_PAREN => {
                 let expr = self.expression();
@@ -162,67 +248,109 @@ impl Parser {
                 return Expression::Grouping(Grouping::new(Box::new(expr)));
             }
             TokenType::TOKEN_IDENTIFIER => {
-                let identifier = token.lexeme.clone();
+                let identifier = token.clone();
                 return Expression::Identifier(Identifier::new(identifier));
             }
-            TokenType::TOKEN_IDENTITY => {
-                self.consume(
-                    TokenType::TOKEN_LEFT_PAREN,
-                    "Expect '(' following identity keyword",
-                );
-                self.consume(
-                    TokenType::TOKEN_NUMBER,
-                    "Expect size of identity as (n) where n is a positive integer.",
-                );
-                let rows = self.previous().lexeme.parse::<f64>().unwrap();
-                if rows < 0.0 {
-                    self.parse_error("Identity cannot be negative sized");
-                    return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
-                }
-                if rows < 1.0 && self.match_token(TokenType::TOKEN_RIGHT_PAREN) {
-                    return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
-                }
-                if rows >= 1.0 && rows == (rows as u32) as f64 {
-                    self.consume(
-                        TokenType::TOKEN_RIGHT_PAREN,
-                        "Expect ')' to close identity matrix dimensions.",
-                    );
-                    return Expression::Literal(Literal::new(Value::wrap_matrix(Matrix::new_identity(rows as usize))));
-                } else {
-                    self.parse_error(
-                        "Identity should be of size (n) where n is a positive integer.",
-                    );
-                    return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
-                }
-            }
-            TokenType::TOKEN_REF | TokenType::TOKEN_RREF | TokenType::TOKEN_INVERSE => {
-                // type checked at runtime rather than compile time
-                // i may need to move the environment
-                let operator = self.previous().clone();
-                self.consume(
-                    TokenType::TOKEN_LEFT_PAREN,
-                    "Expect '(' following ref keyword",
-                );
-                let expr = self.expression();
-                self.consume(TokenType::TOKEN_RIGHT_PAREN, "Expect ')' to close ref");
-                return Expression::Unary(Unary::new(operator, Box::new(expr)));
+            TokenType::TOKEN_ERROR => {
+                let token = token.clone();
+                self.error_at(&token, &token.lexeme);
+                return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
             }
             _ => {
-                self.parse_error("Unexpected token");
+                let token = token.clone();
+                self.error_at(&token, "Unexpected token");
                 return Expression::Literal(Literal::new(Value::new_scalar(0.0)));
             }
         };
         Expression::Literal(Literal::new(value))
     }
 
+    /// The type of the token `offset` places ahead, EOF past the end.
+    fn peek_at(&self, offset: usize) -> &TokenType {
+        let i = (self.current + offset).min(self.tokens.len() - 1);
+        &self.tokens[i].token_type
+    }
+
+    /// Looks ahead for `x ->` or `(x, y) ->` without consuming anything.
+    fn is_lambda(&self) -> bool {
+        let mut offset = 0;
+        match self.peek_at(0) {
+            TokenType::TOKEN_IDENTIFIER => offset += 1,
+            TokenType::TOKEN_LEFT_PAREN => {
+                offset += 1;
+                while *self.peek_at(offset) == TokenType::TOKEN_IDENTIFIER {
+                    offset += 1;
+                    if *self.peek_at(offset) != TokenType::TOKEN_COMMA {
+                        break;
+                    }
+                    offset += 1;
+                }
+                if *self.peek_at(offset) != TokenType::TOKEN_RIGHT_PAREN {
+                    return false;
+                }
+                offset += 1;
+            }
+            _ => return false,
+        }
+        *self.peek_at(offset) == TokenType::TOKEN_ARROW
+    }
+
+    fn lambda(&mut self) -> Expression {
+        let mut params = Vec::new();
+        if self.match_token(TokenType::TOKEN_LEFT_PAREN) {
+            while self.match_token(TokenType::TOKEN_IDENTIFIER) {
+                params.push(self.previous().clone());
+                if !self.match_token(TokenType::TOKEN_COMMA) {
+                    break;
+                }
+            }
+            self.consume(
+                TokenType::TOKEN_RIGHT_PAREN,
+                "Expected ')' after parameters",
+            );
+        } else {
+            self.advance();
+            params.push(self.previous().clone());
+        }
+        self.consume(TokenType::TOKEN_ARROW, "Expected '->' after parameters");
+        let arrow = self.previous().clone();
+        let value = self.expression();
+        Expression::Lambda(Lambda::new(
+            arrow,
+            params,
+            vec![Statement::Return(ReturnStatement::new(Box::new(value)))],
+        ))
+    }
+
     fn unary(&mut self) -> Expression {
-        if self.match_token(TokenType::TOKEN_MINUS) {
+        if self.match_token(TokenType::TOKEN_MINUS) || self.match_token(TokenType::TOKEN_BANG) {
             let operator = self.previous().clone();
             let right = self.unary();
             return Expression::Unary(Unary::new(operator, Box::new(right)));
         }
 
-        self.literal()
+        self.call()
+    }
+
+    fn call(&mut self) -> Expression {
+        let mut expr = self.literal();
+
+        while self.match_token(TokenType::TOKEN_LEFT_PAREN) {
+            let mut arguments = Vec::new();
+            if !self.check(TokenType::TOKEN_RIGHT_PAREN) {
+                loop {
+                    arguments.push(self.expression());
+                    if !self.match_token(TokenType::TOKEN_COMMA) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::TOKEN_RIGHT_PAREN, "Expect ')' after arguments");
+            let paren = self.previous().clone();
+            expr = Expression::Call(Call::new(Box::new(expr), paren, arguments));
+        }
+
+        expr
     }
 
     fn exponentiation(&mut self) -> Expression {
@@ -292,79 +420,361 @@ impl Parser {
         expr
     }
 
+    fn and(&mut self) -> Expression {
+        let mut expr = self.equality();
+        while self.match_token(TokenType::TOKEN_AND) {
+            let operator = self.previous().clone();
+            let right = self.equality();
+            expr = Expression::Logical(Logical::new(Box::new(expr), operator, Box::new(right)));
+        }
+        expr
+    }
+
+    fn or(&mut self) -> Expression {
+        let mut expr = self.and();
+        while self.match_token(TokenType::TOKEN_OR) {
+            let operator = self.previous().clone();
+            let right = self.and();
+            expr = Expression::Logical(Logical::new(Box::new(expr), operator, Box::new(right)));
+        }
+        expr
+    }
+
     fn expression(&mut self) -> Expression {
-        self.equality()
+        if self.is_lambda() {
+            return self.lambda();
+        }
+        self.or()
     }
 
     fn print_statement(&mut self) -> Statement {
-        let value = self.expression();
+        let keyword = self.previous().clone();
+        let mut values = vec![self.expression()];
+        while self.match_token(TokenType::TOKEN_COMMA) {
+            values.push(self.expression());
+        }
         if self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after value") {
-            return Statement::Print(PrintStatement::new(Box::new(value)));
+            Statement::Print(PrintStatement::new(keyword, values))
         } else {
-            return Statement::Expression(ExpressionStatement::new(Box::new(Literal::new(
-                Value::new_scalar(0.0),
-            ))));
+            Statement::Expression(ExpressionStatement::new(Box::new(Expression::Literal(
+                Literal::new(Value::new_scalar(0.0)),
+            ))))
         }
     }
 
-    fn expression_statement(&mut self) -> Statement {
-        let estmt = Statement::Expression {
-            0: ExpressionStatement::new(Box::new(self.expression())),
+    fn explain_statement(&mut self) -> Statement {
+        let keyword = self.previous().clone();
+        let value = self.expression();
+        self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after value");
+        Statement::Explain(ExplainStatement::new(keyword, Box::new(value)))
+    }
+
+    fn set_statement(&mut self) -> Statement {
+        let mut name = self.peek().clone();
+        if self.consume(
+            TokenType::TOKEN_IDENTIFIER,
+            "Expected setting name after set",
+        ) {
+            name = self.previous().clone();
+        }
+        let value = self.expression();
+        self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after value");
+        Statement::Set(SetStatement::new(name, Box::new(value)))
+    }
+
+    fn function_statement(&mut self) -> Statement {
+        let name = self.peek().clone();
+        self.consume(
+            TokenType::TOKEN_IDENTIFIER,
+            "Expected function name after fn",
+        );
+        self.consume(
+            TokenType::TOKEN_LEFT_PAREN,
+            "Expected '(' after function name",
+        );
+        let mut params = Vec::new();
+        if !self.check(TokenType::TOKEN_RIGHT_PAREN) {
+            loop {
+                if self.consume(TokenType::TOKEN_IDENTIFIER, "Expected parameter name") {
+                    params.push(self.previous().clone());
+                }
+                if !self.match_token(TokenType::TOKEN_COMMA) {
+                    break;
+                }
+            }
+        }
+        self.consume(
+            TokenType::TOKEN_RIGHT_PAREN,
+            "Expected ')' after parameters",
+        );
+
+        self.function_depth += 1;
+        let body = if self.match_token(TokenType::TOKEN_EQUAL) {
+            // fn f(x) = expr; is short for fn f(x) { return expr; }
+            let value = self.expression();
+            self.consume(
+                TokenType::TOKEN_SEMICOLON,
+                "Expected ';' after function body",
+            );
+            vec![Statement::Return(ReturnStatement::new(Box::new(value)))]
+        } else {
+            self.consume(
+                TokenType::TOKEN_LEFT_BRACE,
+                "Expected '=' or '{' after parameters",
+            );
+            self.block()
         };
+        self.function_depth -= 1;
+        Statement::Function(FunctionStatement::new(name, params, body))
+    }
+
+    fn block(&mut self) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        while !self.check(TokenType::TOKEN_RIGHT_BRACE) && !self.is_at_end() {
+            statements.push(self.statement());
+        }
+        self.consume(TokenType::TOKEN_RIGHT_BRACE, "Expected '}' after block");
+        statements
+    }
+
+    fn if_statement(&mut self) -> Statement {
+        let keyword = self.previous().clone();
+        let condition = self.expression();
+        self.consume(
+            TokenType::TOKEN_LEFT_BRACE,
+            "Expected '{' after if condition",
+        );
+        let then_branch = self.block();
+        let mut else_branch = Vec::new();
+        if self.match_token(TokenType::TOKEN_ELSE) {
+            if self.match_token(TokenType::TOKEN_IF) {
+                else_branch.push(self.if_statement());
+            } else {
+                self.consume(TokenType::TOKEN_LEFT_BRACE, "Expected '{' after else");
+                else_branch = self.block();
+            }
+        }
+        Statement::If(IfStatement::new(
+            keyword,
+            Box::new(condition),
+            then_branch,
+            else_branch,
+        ))
+    }
+
+    fn while_statement(&mut self) -> Statement {
+        let keyword = self.previous().clone();
+        let condition = self.expression();
+        self.consume(
+            TokenType::TOKEN_LEFT_BRACE,
+            "Expected '{' after while condition",
+        );
+        let body = self.block();
+        Statement::While(WhileStatement::new(keyword, Box::new(condition), body))
+    }
+
+    fn for_statement(&mut self) -> Statement {
+        let variable = self.peek().clone();
+        self.consume(
+            TokenType::TOKEN_IDENTIFIER,
+            "Expected loop variable after for",
+        );
+        self.consume(TokenType::TOKEN_IN, "Expected 'in' after loop variable");
+        let start = self.expression();
+        let range = self.peek().clone();
+        self.consume(
+            TokenType::TOKEN_DOT_DOT,
+            "Expected '..' between the bounds of the range",
+        );
+        let end = self.expression();
+        self.consume(TokenType::TOKEN_LEFT_BRACE, "Expected '{' after range");
+        let body = self.block();
+        Statement::For(ForStatement::new(
+            variable,
+            range,
+            Box::new(start),
+            Box::new(end),
+            body,
+        ))
+    }
+
+    fn return_statement(&mut self) -> Statement {
+        let keyword = self.previous().clone();
+        if self.function_depth == 0 {
+            self.error_at(&keyword, "Cannot return from outside a function");
+        }
+        let value = self.expression();
         self.consume(
             TokenType::TOKEN_SEMICOLON,
-            "Expect ';' after expression statement",
+            "Expected ';' after return value",
         );
+        Statement::Return(ReturnStatement::new(Box::new(value)))
+    }
+
+    fn expression_statement(&mut self) -> Statement {
+        let estmt = Statement::Expression(ExpressionStatement::new(Box::new(self.expression())));
+        if !(self.trailing_expression && self.is_at_end()) {
+            self.consume(
+                TokenType::TOKEN_SEMICOLON,
+                "Expect ';' after expression statement",
+            );
+        }
         estmt
     }
 
-    fn let_statement(&mut self) -> Statement {
-        let mut name = Token::new(TokenType::TOKEN_IDENTIFIER, "".to_string(), 0);
-        if self.consume(TokenType::TOKEN_IDENTIFIER, "Expected identifier") {
-            name = self.previous().clone();
+    fn let_statement(&mut self, constant: bool) -> Statement {
+        // let a = ...; or let P, D = ...; to unpack a tuple, const works the same way
+        let mut names = Vec::new();
+        loop {
+            if self.consume(TokenType::TOKEN_IDENTIFIER, "Expected identifier") {
+                names.push(self.previous().clone());
+            }
+            if !self.match_token(TokenType::TOKEN_COMMA) {
+                break;
+            }
         }
         if self.consume(TokenType::TOKEN_EQUAL, "Expected '=' after identifier") {
             let value = self.expression();
             if self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after value") {
-                return Statement::Let(LetStatement::new(name, Box::new(value)));
+                Statement::Let(LetStatement::new(names, Box::new(value), constant))
             } else {
-                return Statement::Expression(ExpressionStatement::new(Box::new(Literal::new(
-                    Value::new_scalar(0.0),
-                ))));
+                Statement::Expression(ExpressionStatement::new(Box::new(Expression::Literal(
+                    Literal::new(Value::new_scalar(0.0)),
+                ))))
             }
         } else {
-            return Statement::Expression(ExpressionStatement::new(Box::new(Literal::new(
-                Value::new_scalar(0.0),
-            ))));
+            Statement::Expression(ExpressionStatement::new(Box::new(Expression::Literal(
+                Literal::new(Value::new_scalar(0.0)),
+            ))))
         }
     }
 
+    fn assign_statement(&mut self) -> Statement {
+        let name = self.advance().clone();
+        let operator = self.advance().clone();
+        let mut value = self.expression();
+        self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after value");
+        // x += e is x = x + e, errors point at the +=
+        let token_type = match operator.token_type {
+            TokenType::TOKEN_PLUS_EQUAL => Some(TokenType::TOKEN_PLUS),
+            TokenType::TOKEN_MINUS_EQUAL => Some(TokenType::TOKEN_MINUS),
+            TokenType::TOKEN_STAR_EQUAL => Some(TokenType::TOKEN_STAR),
+            _ => None,
+        };
+        if let Some(token_type) = token_type {
+            value = Expression::Binary(Binary::new(
+                Box::new(Expression::Identifier(Identifier::new(name.clone()))),
+                Token::new(token_type, operator.lexeme, operator.span),
+                Box::new(value),
+            ));
+        }
+        Statement::Assign(AssignStatement::new(name, Box::new(value)))
+    }
+
+    /// An identifier followed by =, +=, -= or *=.
+    fn is_assignment(&self) -> bool {
+        *self.peek_at(0) == TokenType::TOKEN_IDENTIFIER
+            && matches!(
+                self.peek_at(1),
+                TokenType::TOKEN_EQUAL
+                    | TokenType::TOKEN_PLUS_EQUAL
+                    | TokenType::TOKEN_MINUS_EQUAL
+                    | TokenType::TOKEN_STAR_EQUAL
+            )
+    }
+
     fn new_line_statement(&mut self) -> Statement {
+        let keyword = self.previous().clone();
         let mut lines: usize = 1;
         if self.match_token(TokenType::TOKEN_NUMBER) {
-            let token = self.previous();
-            match token.lexeme.parse::<usize>() {
-                Ok(n) => lines = n,
-                Err(_) => self.parse_error("Expected number after newline"),
+            let n = parse_number(&self.previous().lexeme);
+            if n >= 0.0 && n.fract() == 0.0 {
+                lines = n as usize;
+            } else {
+                self.parse_error("Expected number after newline");
             }
         }
         self.consume(TokenType::TOKEN_SEMICOLON, "Expected ';' after newline");
-        Statement::NewLine(NewLineStatement::new(lines))
+        Statement::NewLine(NewLineStatement::new(keyword, lines))
+    }
+
+    /// A let, const or fn with /// comments above it.
+    fn documented_statement(&mut self) -> Statement {
+        let mut lines = Vec::new();
+        while self.match_token(TokenType::TOKEN_DOC_COMMENT) {
+            lines.push(self.previous().lexeme.clone());
+        }
+        let doc = Some(lines.join("\n"));
+        let mut statement = self.statement();
+        match &mut statement {
+            Statement::Let(statement) => statement.doc = doc,
+            Statement::Function(statement) => statement.doc = doc,
+            _ => (),
+        }
+        statement
     }
 
     fn statement(&mut self) -> Statement {
-        if self.match_token(TokenType::TOKEN_PRINT) {
+        if self.check(TokenType::TOKEN_DOC_COMMENT) {
+            return self.documented_statement();
+        } else if self.match_token(TokenType::TOKEN_PRINT) {
             return self.print_statement();
         } else if self.match_token(TokenType::TOKEN_LET) {
-            return self.let_statement();
+            return self.let_statement(false);
+        } else if self.match_token(TokenType::TOKEN_CONST) {
+            return self.let_statement(true);
         } else if self.match_token(TokenType::TOKEN_NEWLINE) {
             return self.new_line_statement();
+        } else if self.match_token(TokenType::TOKEN_EXPLAIN) {
+            return self.explain_statement();
+        } else if self.match_token(TokenType::TOKEN_SET) {
+            return self.set_statement();
+        } else if self.match_token(TokenType::TOKEN_FN) {
+            return self.function_statement();
+        } else if self.match_token(TokenType::TOKEN_RETURN) {
+            return self.return_statement();
+        } else if self.match_token(TokenType::TOKEN_IF) {
+            return self.if_statement();
+        } else if self.match_token(TokenType::TOKEN_WHILE) {
+            return self.while_statement();
+        } else if self.match_token(TokenType::TOKEN_FOR) {
+            return self.for_statement();
+        } else if self.is_assignment() {
+            return self.assign_statement();
         }
 
         self.expression_statement()
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Statement>, String> {
+    /// Parses a lone literal like `-2.5` or `[1 2 | 3 4]`, for `str::parse`.
+    pub fn parse_value(&mut self) -> Result<Value, Vec<Diagnostic>> {
+        let negative = self.match_token(TokenType::TOKEN_MINUS);
+        let start = self.peek().clone();
+        let value = if self.is_at_end() || (negative && !self.check(TokenType::TOKEN_NUMBER)) {
+            None
+        } else {
+            match self.literal() {
+                Expression::Literal(literal) => Some(literal.value),
+                _ => None,
+            }
+        };
+        match value {
+            None => self.error_at(&start, "Expected a number, boolean, string or matrix"),
+            Some(_) if !self.is_at_end() => self.parse_error("Expected only one value"),
+            Some(_) => (),
+        }
+        match value {
+            Some(value) if self.errors.is_empty() => match value.data {
+                ValueType::SCALAR(s) if negative => Ok(Value::new_scalar(-s.data)),
+                _ => Ok(value),
+            },
+            _ => Err(std::mem::take(&mut self.errors)),
+        }
+    }
+
+    /// Parses every statement, returning all syntax errors found if there
+    /// were any.
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<Diagnostic>> {
         let mut statements: Vec<Statement> = Vec::new();
         while !self.is_at_end() {
             statements.push(self.statement());
@@ -373,6 +783,10 @@ impl Parser {
                 self.synchronize();
             }
         }
-        Ok(statements)
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
 }
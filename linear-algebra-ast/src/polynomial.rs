@@ -0,0 +1,230 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+const MAX_ITERATIONS: usize = 1000;
+const EPSILON: f64 = 0.0000000001;
+// repeated roots only converge to about eps^(1/multiplicity), so roots this
+// close together may be one repeated root, which verify_cluster then checks
+const CLUSTER_RADIUS: f64 = 0.0001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, other: Complex) -> Complex {
+        let denominator = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+}
+
+/// A polynomial stored by its coefficients, highest degree first, so
+/// `[1 -3 2]` is x^2 - 3x + 2.
+#[derive(Clone)]
+pub struct Polynomial {
+    pub coefficients: Vec<f64>,
+}
+
+impl Polynomial {
+    pub fn new(coefficients: Vec<f64>) -> Self {
+        // leading zeros do not change the polynomial, only its apparent degree
+        let first = coefficients
+            .iter()
+            .position(|c| *c != 0.0)
+            .unwrap_or(coefficients.len());
+        Polynomial {
+            coefficients: coefficients[first..].to_vec(),
+        }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn derivative(&self) -> Polynomial {
+        let degree = self.degree();
+        let mut coefficients = Vec::new();
+        for (i, c) in self.coefficients.iter().take(degree).enumerate() {
+            coefficients.push(c * (degree - i) as f64);
+        }
+        Polynomial::new(coefficients)
+    }
+
+    fn evaluate(&self, x: Complex) -> Complex {
+        // horner's method
+        let mut result = Complex::new(0.0, 0.0);
+        for c in &self.coefficients {
+            result = result * x + Complex::new(*c, 0.0);
+        }
+        result
+    }
+
+    /// Refines an estimate of a simple root with newton's method.
+    fn newton(&self, mut x: Complex) -> Complex {
+        let slope = self.derivative();
+        for _ in 0..MAX_ITERATIONS {
+            let delta = self.evaluate(x) / slope.evaluate(x);
+            if !delta.re.is_finite() || !delta.im.is_finite() {
+                break;
+            }
+            x = x - delta;
+            if delta.norm() < EPSILON {
+                break;
+            }
+        }
+        x
+    }
+
+    /// Whether `x` is a root of multiplicity `multiplicity`: the polynomial
+    /// and its derivatives below that order all vanish there, up to rounding.
+    /// Rounding alone spreads a true m-fold root by about eps^(1/m), leaving
+    /// the kth derivative at about eps^((m-k)/m), distinct roots further apart
+    /// leave more.
+    fn is_multiple_root(&self, x: Complex, multiplicity: usize) -> bool {
+        let mut derivative = self.clone();
+        for k in 0..multiplicity - 1 {
+            let bound = derivative.magnitude(x.norm())
+                * f64::EPSILON.powf((multiplicity - k) as f64 / multiplicity as f64);
+            if derivative.evaluate(x).norm() > bound {
+                return false;
+            }
+            derivative = derivative.derivative();
+        }
+        true
+    }
+
+    /// The size of the terms when evaluating at a point of norm `r`, what
+    /// rounding error in the value is relative to.
+    fn magnitude(&self, r: f64) -> f64 {
+        let r = r.max(1.0);
+        self.coefficients
+            .iter()
+            .fold(0.0, |sum, c| sum * r + c.abs())
+    }
+
+    /// Finds every root of the polynomial with the Durand-Kerner method.
+    /// Roots are sorted by real part, then imaginary part, both descending.
+    pub fn roots(&self) -> Vec<Complex> {
+        let mut roots = Vec::new();
+        if self.coefficients.is_empty() {
+            return roots;
+        }
+
+        // factor out roots at zero exactly, they converge poorly otherwise
+        let mut coefficients = self.coefficients.clone();
+        while coefficients.len() > 1 && coefficients[coefficients.len() - 1] == 0.0 {
+            coefficients.pop();
+            roots.push(Complex::new(0.0, 0.0));
+        }
+
+        let leading = coefficients[0];
+        let monic = Polynomial {
+            coefficients: coefficients.iter().map(|c| c / leading).collect(),
+        };
+        let degree = monic.degree();
+
+        // standard starting points, powers of a number that is neither real nor a root of unity
+        let seed = Complex::new(0.4, 0.9);
+        let mut guesses = Vec::new();
+        let mut guess = Complex::new(1.0, 0.0);
+        for _ in 0..degree {
+            guesses.push(guess);
+            guess = guess * seed;
+        }
+
+        for _ in 0..MAX_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for i in 0..degree {
+                let mut denominator = Complex::new(1.0, 0.0);
+                for j in 0..degree {
+                    if i != j {
+                        denominator = denominator * (guesses[i] - guesses[j]);
+                    }
+                }
+                let delta = monic.evaluate(guesses[i]) / denominator;
+                guesses[i] = guesses[i] - delta;
+                change = change.max(delta.norm());
+            }
+            if change < EPSILON {
+                break;
+            }
+        }
+
+        // the mean of a cluster is far more accurate than any of its members
+        let mut clustered = vec![false; degree];
+        for i in 0..degree {
+            if clustered[i] {
+                continue;
+            }
+            let radius = CLUSTER_RADIUS * guesses[i].norm().max(1.0);
+            let members: Vec<usize> = (i..degree)
+                .filter(|j| !clustered[*j] && (guesses[*j] - guesses[i]).norm() < radius)
+                .collect();
+            let mut mean = Complex::new(0.0, 0.0);
+            for j in &members {
+                mean = mean + guesses[*j];
+            }
+            mean = mean / Complex::new(members.len() as f64, 0.0);
+            // a root of multiplicity m is a simple root of the (m-1)th derivative,
+            // where newton's method converges quickly again
+            let mut target = monic.clone();
+            for _ in 1..members.len() {
+                target = target.derivative();
+            }
+            mean = target.newton(mean);
+            // close but distinct roots are only polished, a cluster is replaced
+            // by its mean once the derivatives confirm it is one repeated root
+            if members.len() > 1 && !monic.is_multiple_root(mean, members.len()) {
+                guesses[i] = monic.newton(guesses[i]);
+                continue;
+            }
+            for j in members {
+                guesses[j] = mean;
+                clustered[j] = true;
+            }
+        }
+
+        roots.extend(guesses);
+        roots.sort_by(|a, b| b.re.total_cmp(&a.re).then(b.im.total_cmp(&a.im)));
+        roots
+    }
+}
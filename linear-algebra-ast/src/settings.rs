@@ -0,0 +1,55 @@
+/// Interpreter wide numeric settings, changed from scripts with
+/// `set <name> <value>;` or from the command line with `--<name> <value>`.
+#[derive(Clone)]
+pub struct Settings {
+    /// Pivots and entries smaller than this are treated as zero by row
+    /// reduction, inverses, ranks and eigenvalue checks.
+    pub tolerance: f64,
+    /// Entries smaller than this are printed as 0, 0 turns snapping off.
+    pub snap: f64,
+    /// How many significant digits print shows at most, trailing zeros are
+    /// dropped.
+    pub precision: usize,
+}
+
+/// More significant digits than a float can hold.
+const MAX_PRECISION: usize = 15;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::new()
+    }
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            tolerance: 0.0000001,
+            snap: 0.0000000001,
+            precision: 10,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) -> Result<(), String> {
+        if value.is_nan() || value < 0.0 {
+            return Err(format!("Setting {} must be a non-negative number", name));
+        }
+        match name {
+            "tolerance" => self.tolerance = value,
+            "snap" => self.snap = value,
+            "precision"
+                if value.fract() == 0.0 && value >= 1.0 && value <= MAX_PRECISION as f64 =>
+            {
+                self.precision = value as usize
+            }
+            "precision" => {
+                return Err(format!(
+                    "Setting precision must be a whole number from 1 to {}",
+                    MAX_PRECISION
+                ))
+            }
+            _ => return Err(format!("Unknown setting {}", name)),
+        }
+        Ok(())
+    }
+}
@@ -1,25 +1,52 @@
 use std::fmt;
 
-#[derive(Clone)]
+/// Where a token sits in the source. `start` and `end` are byte offsets,
+/// `line` and `column` start at 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Self {
+    pub fn new(token_type: TokenType, lexeme: String, span: Span) -> Self {
         Token {
             token_type,
             lexeme,
-            line,
+            span,
         }
     }
 }
 
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?} - {} - {}", self.token_type, self.lexeme, self.line)
+        write!(
+            f,
+            "{:?} - {} - {}",
+            self.token_type,
+            self.lexeme.escape_debug(),
+            self.span.line
+        )
     }
 }
 
@@ -29,6 +56,8 @@ pub enum TokenType {
     TOKEN_EOF,
     TOKEN_ERROR,
     TOKEN_NUMBER,
+    TOKEN_STRING,
+    TOKEN_DOC_COMMENT,
     TOKEN_PLUS,
     TOKEN_MINUS,
     TOKEN_STAR,
@@ -43,6 +72,7 @@ pub enum TokenType {
     TOKEN_RIGHT_BRACKET,
     TOKEN_COMMA,
     TOKEN_DOT,
+    TOKEN_DOT_DOT,
     TOKEN_SEMICOLON,
     TOKEN_GREATER,
     TOKEN_GREATER_EQUAL,
@@ -52,13 +82,27 @@ pub enum TokenType {
     TOKEN_EQUAL_EQUAL,
     TOKEN_BANG,
     TOKEN_BANG_EQUAL,
+    TOKEN_PLUS_EQUAL,
+    TOKEN_MINUS_EQUAL,
+    TOKEN_STAR_EQUAL,
     TOKEN_PIPE,
+    TOKEN_ARROW,
     TOKEN_IDENTIFIER,
     TOKEN_PRINT,
     TOKEN_NEWLINE,
     TOKEN_LET,
-    TOKEN_IDENTITY,
-    TOKEN_REF,
-    TOKEN_RREF,
-    TOKEN_INVERSE
+    TOKEN_CONST,
+    TOKEN_EXPLAIN,
+    TOKEN_SET,
+    TOKEN_FN,
+    TOKEN_RETURN,
+    TOKEN_IF,
+    TOKEN_ELSE,
+    TOKEN_WHILE,
+    TOKEN_FOR,
+    TOKEN_IN,
+    TOKEN_TRUE,
+    TOKEN_FALSE,
+    TOKEN_AND,
+    TOKEN_OR,
 }
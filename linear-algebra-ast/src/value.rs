@@ -1,10 +1,150 @@
+use std::fmt;
+use std::str::FromStr;
+use std::rc::Rc;
+
+use crate::analyzer::Shape;
+use crate::builtins::Builtin;
+use crate::function::Function;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::polynomial::Polynomial;
+use crate::settings::Settings;
+
 #[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum ValueType {
     SCALAR(Scalar),
     MATRIX(Matrix),
+    TUPLE(Vec<Value>),
+    FUNCTION(Rc<Function>),
+    BUILTIN(Rc<Builtin>),
+    BOOL(bool),
+    STRING(String),
+}
+
+/// Whether `a` and `b` are within `tolerance` of each other. Equal values
+/// always are, even infinite ones whose difference is NaN.
+pub fn nearly_equal(a: f64, b: f64, tolerance: f64) -> bool {
+    a == b || (a - b).abs() <= tolerance
 }
 
+/// Functions only show their name and are equal when they are the same
+/// function, their bodies are not compared.
+impl fmt::Debug for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValueType::SCALAR(s) => f.debug_tuple("SCALAR").field(s).finish(),
+            ValueType::MATRIX(m) => f.debug_tuple("MATRIX").field(m).finish(),
+            ValueType::TUPLE(values) => f.debug_tuple("TUPLE").field(values).finish(),
+            ValueType::FUNCTION(function) => f
+                .debug_tuple("FUNCTION")
+                .field(&function.name.lexeme)
+                .finish(),
+            ValueType::BUILTIN(builtin) => f.debug_tuple("BUILTIN").field(&builtin.name).finish(),
+            ValueType::BOOL(b) => f.debug_tuple("BOOL").field(b).finish(),
+            ValueType::STRING(s) => f.debug_tuple("STRING").field(s).finish(),
+        }
+    }
+}
+
+impl PartialEq for ValueType {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ValueType::SCALAR(a), ValueType::SCALAR(b)) => a == b,
+            (ValueType::MATRIX(a), ValueType::MATRIX(b)) => a == b,
+            (ValueType::TUPLE(a), ValueType::TUPLE(b)) => a == b,
+            (ValueType::FUNCTION(a), ValueType::FUNCTION(b)) => Rc::ptr_eq(a, b),
+            (ValueType::BUILTIN(a), ValueType::BUILTIN(b)) => Rc::ptr_eq(a, b),
+            (ValueType::BOOL(a), ValueType::BOOL(b)) => a == b,
+            (ValueType::STRING(a), ValueType::STRING(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// `x` rounded to `digits` significant digits, with -0 as 0.
+pub fn round_to(x: f64, digits: usize) -> f64 {
+    if x == 0.0 || !x.is_finite() {
+        return x + 0.0;
+    }
+    let text = format!("{:.*e}", digits.max(1) - 1, x);
+    text.parse().unwrap_or(x) + 0.0
+}
+
+/// `x` rounded to `decimals` decimals, with -0 as 0. Numbers too large to
+/// have that many decimals in a float are left as they are.
+fn round_decimals(x: f64, decimals: usize) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    let rounded = if (x * scale).abs() < 2f64.powi(52) {
+        (x * scale).round() / scale
+    } else {
+        x
+    };
+    if rounded == 0.0 {
+        0.0
+    } else {
+        rounded
+    }
+}
+
+/// An elementary row operation, rows are zero indexed.
 #[derive(Clone)]
+pub enum RowOperation {
+    Swap(usize, usize),
+    Scale(usize, f64),
+    /// Add(target, source, factor) is target <- target + factor * source
+    Add(usize, usize, f64),
+}
+
+impl RowOperation {
+    /// The same operation with its factor rounded for printing.
+    pub fn rounded(self, precision: usize) -> Self {
+        match self {
+            RowOperation::Swap(a, b) => RowOperation::Swap(a, b),
+            RowOperation::Scale(row, factor) => {
+                RowOperation::Scale(row, round_to(factor, precision))
+            }
+            RowOperation::Add(target, source, factor) => {
+                RowOperation::Add(target, source, round_to(factor, precision))
+            }
+        }
+    }
+}
+
+impl fmt::Display for RowOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RowOperation::Swap(a, b) => write!(f, "R{} ↔ R{}", a + 1, b + 1),
+            RowOperation::Scale(row, factor) => write!(f, "R{} ← {}R{}", row + 1, factor, row + 1),
+            RowOperation::Add(target, source, factor) => {
+                let sign = if *factor < 0.0 { "−" } else { "+" };
+                let magnitude = factor.abs();
+                if magnitude == 1.0 {
+                    write!(
+                        f,
+                        "R{} ← R{} {} R{}",
+                        target + 1,
+                        target + 1,
+                        sign,
+                        source + 1
+                    )
+                } else {
+                    write!(
+                        f,
+                        "R{} ← R{} {} {}R{}",
+                        target + 1,
+                        target + 1,
+                        sign,
+                        magnitude,
+                        source + 1
+                    )
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Matrix {
     pub data: Vec<f64>,
     pub rows: usize,
@@ -39,12 +179,41 @@ impl Matrix {
             }
         }
         self.data = new_data;
-        let temp = self.rows;
-        self.rows = self.cols;
-        self.cols = temp;
+        std::mem::swap(&mut self.rows, &mut self.cols);
+    }
+
+    pub fn apply(&mut self, operation: &RowOperation) {
+        match *operation {
+            RowOperation::Swap(a, b) => {
+                for k in 0..self.cols {
+                    self.data.swap(a * self.cols + k, b * self.cols + k);
+                }
+            }
+            RowOperation::Scale(row, factor) => {
+                for k in 0..self.cols {
+                    self.data[row * self.cols + k] *= factor;
+                }
+            }
+            RowOperation::Add(target, source, factor) => {
+                for k in 0..self.cols {
+                    self.data[target * self.cols + k] += factor * self.data[source * self.cols + k];
+                }
+            }
+        }
+    }
+
+    pub fn ref_matrix(&mut self, tolerance: f64) {
+        self.ref_matrix_recorded(tolerance, &mut |_, _| {});
     }
 
-    pub fn ref_matrix(&mut self) {
+    /// Row echelon form, calling `record` after every elementary row
+    /// operation with the matrix as it stands after that operation.
+    /// Pivots smaller than `tolerance` are treated as zero.
+    pub fn ref_matrix_recorded(
+        &mut self,
+        tolerance: f64,
+        record: &mut dyn FnMut(RowOperation, &Matrix),
+    ) {
         // convert to row echelon form (pivots need not be 1)
         let mut i = 0;
         let mut j = 0;
@@ -55,18 +224,22 @@ impl Matrix {
                     max_row = k;
                 }
             }
-            if self.data[max_row * self.cols + j].abs() < 0.0000001 {
+            if self.data[max_row * self.cols + j].abs() < tolerance {
                 j += 1;
                 continue;
             }
-            for k in 0..self.cols {
-                let temp = self.data[i * self.cols + k];
-                self.data[i * self.cols + k] = self.data[max_row * self.cols + k];
-                self.data[max_row * self.cols + k] = temp;
+            if max_row != i {
+                for k in 0..self.cols {
+                    self.data.swap(i * self.cols + k, max_row * self.cols + k);
+                }
+                record(RowOperation::Swap(i, max_row), self);
             }
             for k in 0..self.rows {
                 if k != i {
                     let c = -self.data[k * self.cols + j] / self.data[i * self.cols + j];
+                    if c == 0.0 {
+                        continue;
+                    }
                     for l in 0..self.cols {
                         if l == j {
                             self.data[k * self.cols + l] = 0.0;
@@ -74,6 +247,7 @@ impl Matrix {
                             self.data[k * self.cols + l] += c * self.data[i * self.cols + l];
                         }
                     }
+                    record(RowOperation::Add(k, i, c), self);
                 }
             }
             i += 1;
@@ -81,23 +255,40 @@ impl Matrix {
         }
     }
 
-    pub fn rref_matrix(&mut self) {
+    pub fn rref_matrix(&mut self, tolerance: f64) {
+        self.rref_matrix_recorded(tolerance, &mut |_, _| {});
+    }
+
+    pub fn rref_matrix_recorded(
+        &mut self,
+        tolerance: f64,
+        record: &mut dyn FnMut(RowOperation, &Matrix),
+    ) {
         // convert to reduced row echelon form (pivots are 1)
-        self.ref_matrix();
+        self.ref_matrix_recorded(tolerance, record);
         for i in 0..self.rows {
             for j in 0..self.cols {
-                if self.data[i * self.cols + j].abs() > 0.0000001 {
+                if self.data[i * self.cols + j].abs() > tolerance {
                     let c = 1.0 / self.data[i * self.cols + j];
                     for k in 0..self.cols {
                         self.data[i * self.cols + k] *= c;
                     }
+                    if c != 1.0 {
+                        record(RowOperation::Scale(i, c), self);
+                    }
                     break;
                 }
             }
         }
     }
 
-    pub fn inverse(&mut self) -> bool {
+    /// Inverts the matrix in place by reducing [A | I], returning false
+    /// (and leaving the matrix reduced) when A is singular.
+    pub fn inverse_recorded(
+        &mut self,
+        tolerance: f64,
+        record: &mut dyn FnMut(RowOperation, &Matrix),
+    ) -> bool {
         if self.rows != self.cols {
             return false;
         }
@@ -113,12 +304,11 @@ impl Matrix {
         }
         self.data = new_data;
         self.cols *= 2;
-        self.rref_matrix();
+        self.rref_matrix_recorded(tolerance, record);
+        // a singular matrix reduces to something other than I on the left
         for i in 0..self.rows {
-            for j in 0..self.cols {
-                if self.data[i * self.cols + j].abs() < 0.001 {
-                    self.data[i * self.cols + j] = 0.0;
-                }
+            if (self.data[i * self.cols + i] - 1.0).abs() > tolerance {
+                return false;
             }
         }
         let mut new_data = Vec::new();
@@ -131,14 +321,211 @@ impl Matrix {
         self.cols /= 2;
         true
     }
+
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        let mut new_data = vec![0.0; self.rows * other.cols];
+        for i in 0..self.rows {
+            for j in 0..other.cols {
+                for k in 0..self.cols {
+                    new_data[i * other.cols + j] +=
+                        self.data[i * self.cols + k] * other.data[k * other.cols + j];
+                }
+            }
+        }
+        Matrix {
+            data: new_data,
+            rows: self.rows,
+            cols: other.cols,
+        }
+    }
+
+    /// Same size with every pair of entries within `tolerance` of each other.
+    pub fn approx_eq(&self, other: &Matrix, tolerance: f64) -> bool {
+        self.rows == other.rows
+            && self.cols == other.cols
+            && self
+                .data
+                .iter()
+                .zip(&other.data)
+                .all(|(a, b)| nearly_equal(*a, *b, tolerance))
+    }
+
+    pub fn trace(&self) -> f64 {
+        let mut sum = 0.0;
+        for i in 0..self.rows.min(self.cols) {
+            sum += self.data[i * self.cols + i];
+        }
+        sum
+    }
+
+    pub fn characteristic_polynomial(&self) -> Polynomial {
+        // faddeev-leverrier, coefficients of det(xI - A) highest degree first
+        let n = self.rows;
+        let mut coefficients = vec![1.0];
+        let mut m = Matrix::new_identity(n);
+        m.scale(0.0);
+        for k in 1..=n {
+            let mut am = self.multiply(&m);
+            let c = coefficients[k - 1];
+            for i in 0..n {
+                am.data[i * n + i] += c;
+            }
+            m = am;
+            coefficients.push(-self.multiply(&m).trace() / k as f64);
+        }
+        Polynomial::new(coefficients)
+    }
+
+    fn shifted(&self, lambda: f64) -> Matrix {
+        // A - lambda * I
+        let mut shifted = self.clone();
+        for i in 0..self.rows {
+            shifted.data[i * self.cols + i] -= lambda;
+        }
+        shifted
+    }
+
+    pub fn rank(&self, tolerance: f64) -> usize {
+        let mut reduced = self.clone();
+        reduced.ref_matrix(tolerance);
+        (0..reduced.rows)
+            .filter(|i| {
+                (0..reduced.cols).any(|j| reduced.data[i * reduced.cols + j].abs() > tolerance)
+            })
+            .count()
+    }
+
+    pub fn null_space(&self, tolerance: f64) -> Vec<Vec<f64>> {
+        let mut reduced = self.clone();
+        reduced.rref_matrix(tolerance);
+        let mut pivots = Vec::new();
+        for i in 0..reduced.rows {
+            for j in 0..reduced.cols {
+                if reduced.data[i * reduced.cols + j].abs() > tolerance {
+                    pivots.push((i, j));
+                    break;
+                }
+            }
+        }
+        // one basis vector per free column, with that free variable set to 1
+        let mut basis = Vec::new();
+        for free in 0..reduced.cols {
+            if pivots.iter().any(|(_, j)| *j == free) {
+                continue;
+            }
+            let mut vector = vec![0.0; reduced.cols];
+            vector[free] = 1.0;
+            for (i, j) in &pivots {
+                let entry = -reduced.data[i * reduced.cols + free];
+                vector[*j] = if entry.abs() < tolerance { 0.0 } else { entry };
+            }
+            basis.push(vector);
+        }
+        basis
+    }
+
+    /// Real eigenvalues grouped with their algebraic multiplicity, or an
+    /// error naming the first complex eigenvalue.
+    fn real_eigenvalues(&self, tolerance: f64) -> Result<Vec<(f64, usize)>, String> {
+        let mut eigenvalues: Vec<(f64, usize)> = Vec::new();
+        for root in self.characteristic_polynomial().roots() {
+            if root.im.abs() > tolerance {
+                return Err(format!(
+                    "Matrix has complex eigenvalue {} + {}i, it has no real spectral form",
+                    root.re, root.im
+                ));
+            }
+            match eigenvalues.last_mut() {
+                Some((lambda, multiplicity)) if (*lambda - root.re).abs() < tolerance => {
+                    *multiplicity += 1;
+                }
+                _ => eigenvalues.push((root.re, 1)),
+            }
+        }
+        Ok(eigenvalues)
+    }
+
+    /// Finds P and D with A = P D P^-1, where D is diagonal and the columns
+    /// of P are eigenvectors of A.
+    pub fn diagonalize(&self, tolerance: f64) -> Result<(Matrix, Matrix), String> {
+        if self.rows != self.cols {
+            return Err("Cannot diagonalize a non-square matrix".to_string());
+        }
+        let n = self.rows;
+        let mut p = Matrix::new_identity(n);
+        let mut d = Matrix::new_identity(n);
+        let mut column = 0;
+        for (lambda, multiplicity) in self.real_eigenvalues(tolerance)? {
+            let shifted = self.shifted(lambda);
+            // eigenvalues are only accurate to the tolerance, so the number of
+            // independent eigenvectors is judged by the same tolerance
+            let geometric = n - shifted.rank(tolerance);
+            if geometric < multiplicity {
+                return Err(format!(
+                    "Matrix is not diagonalizable, eigenvalue {} has algebraic multiplicity {} but geometric multiplicity {}",
+                    lambda, multiplicity, geometric
+                ));
+            }
+            let eigenvectors = shifted.null_space(tolerance);
+            for vector in eigenvectors.iter().take(multiplicity) {
+                for (i, entry) in vector.iter().enumerate() {
+                    p.data[i * n + column] = *entry;
+                }
+                d.data[column * n + column] = lambda;
+                column += 1;
+            }
+        }
+        Ok((p, d))
+    }
+
+    /// Jordan normal form of A. Block sizes for each eigenvalue come from the
+    /// ranks of (A - lambda I)^k, largest blocks first.
+    pub fn jordan(&self, tolerance: f64) -> Result<Matrix, String> {
+        if self.rows != self.cols {
+            return Err("Cannot find the Jordan form of a non-square matrix".to_string());
+        }
+        let n = self.rows;
+        let mut j = Matrix::new_identity(n);
+        j.scale(0.0);
+        let mut offset = 0;
+        for (lambda, multiplicity) in self.real_eigenvalues(tolerance)? {
+            let shifted = self.shifted(lambda);
+            // ranks[k] = rank((A - lambda I)^k)
+            let mut ranks = vec![n];
+            let mut power = Matrix::new_identity(n);
+            for _ in 0..=multiplicity {
+                power = power.multiply(&shifted);
+                ranks.push(power.rank(tolerance));
+            }
+            // blocks of size at least k = ranks[k - 1] - ranks[k]
+            let mut sizes = Vec::new();
+            for k in (1..=multiplicity).rev() {
+                let at_least = ranks[k - 1].saturating_sub(ranks[k]);
+                let larger = ranks[k].saturating_sub(ranks[k + 1]);
+                for _ in 0..at_least.saturating_sub(larger) {
+                    sizes.push(k);
+                }
+            }
+            for size in sizes {
+                for i in 0..size {
+                    j.data[(offset + i) * n + offset + i] = lambda;
+                    if i + 1 < size {
+                        j.data[(offset + i) * n + offset + i + 1] = 1.0;
+                    }
+                }
+                offset += size;
+            }
+        }
+        Ok(j)
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Scalar {
     pub data: f64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Value {
     pub data: ValueType,
 }
@@ -160,29 +547,270 @@ impl Value {
         }
     }
 
+    pub fn new_bool(value: bool) -> Self {
+        Value {
+            data: ValueType::BOOL(value),
+        }
+    }
+
+    pub fn new_string(string: String) -> Self {
+        Value {
+            data: ValueType::STRING(string),
+        }
+    }
+
     pub fn wrap_matrix(matrix: Matrix) -> Self {
         Value {
             data: ValueType::MATRIX(matrix),
         }
     }
 
-    pub fn print(&self) {
+    pub fn wrap_tuple(values: Vec<Value>) -> Self {
+        Value {
+            data: ValueType::TUPLE(values),
+        }
+    }
+
+    pub fn wrap_function(function: Function) -> Self {
+        Value {
+            data: ValueType::FUNCTION(Rc::new(function)),
+        }
+    }
+
+    pub fn wrap_builtin(builtin: Rc<Builtin>) -> Self {
+        Value {
+            data: ValueType::BUILTIN(builtin),
+        }
+    }
+
+    /// What print shows for this value, without the final newline.
+    pub fn to_text(&self, settings: &Settings) -> String {
+        self.rounded(settings).to_string()
+    }
+
+    /// A copy as print shows it: numbers smaller than the snap setting are
+    /// too small to tell apart from rounding error and become 0, the rest are
+    /// rounded to the significant digits of the precision setting.
+    pub fn rounded(&self, settings: &Settings) -> Value {
+        self.snapped(settings, &|x| round_to(x, settings.precision))
+    }
+
+    /// A copy as a placeholder like `{:.2}` shows it, snapped like
+    /// `rounded` and then rounded to `decimals` decimals.
+    pub fn rounded_decimals(&self, settings: &Settings, decimals: usize) -> Value {
+        self.snapped(settings, &|x| round_decimals(x, decimals))
+    }
+
+    fn snapped(&self, settings: &Settings, round: &dyn Fn(f64) -> f64) -> Value {
+        let snap = |x: f64| {
+            if x.abs() < settings.snap {
+                0.0
+            } else {
+                round(x)
+            }
+        };
         match &self.data {
-            ValueType::SCALAR(s) => println!("{}", s.data),
+            ValueType::SCALAR(s) => Value::new_scalar(snap(s.data)),
             ValueType::MATRIX(m) => {
-                for i in 0..m.rows {
-                    for j in 0..m.cols {
-                        if j == 0 {
-                            print!("| ");
-                        }
-                        print!("{} ", m.data[i * m.cols + j]);
-                        if j == m.cols - 1 {
-                            print!("|");
-                        }
+                Value::new_matrix(m.data.iter().map(|&x| snap(x)).collect(), m.rows, m.cols)
+            }
+            ValueType::TUPLE(values) => Value::wrap_tuple(
+                values
+                    .iter()
+                    .map(|value| value.snapped(settings, round))
+                    .collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Rows as `| 1 2 |` lines with every column right aligned to its widest
+/// entry, or the literal `[1 2 | 3 4]` with `{:#}`. Width, precision and
+/// alignment apply to every entry, e.g. `{:>6.2}`.
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.cols == 0 {
+            return f.write_str(if f.alternate() { "[]" } else { "" });
+        }
+        let entries: Vec<String> = self
+            .data
+            .iter()
+            .map(|&entry| number_text(entry, f.precision()))
+            .collect();
+        let mut widths = vec![f.width().unwrap_or(0); self.cols];
+        if !f.alternate() {
+            for (i, entry) in entries.iter().enumerate() {
+                let width = &mut widths[i % self.cols];
+                *width = (*width).max(entry.chars().count());
+            }
+        }
+        for (i, row) in entries.chunks(self.cols).enumerate() {
+            if f.alternate() {
+                f.write_str(if i == 0 { "[" } else { " | " })?;
+            } else {
+                if i > 0 {
+                    f.write_str("\n")?;
+                }
+                f.write_str("| ")?;
+            }
+            for (j, entry) in row.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                pad(f, entry, widths[j])?;
+            }
+            if !f.alternate() {
+                f.write_str(" |")?;
+            }
+        }
+        if f.alternate() {
+            f.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Numbers from this size up print with an exponent, e.g. `6.02e23`.
+const LARGEST_PLAIN: f64 = 1e15;
+/// Numbers below this size print with an exponent, e.g. `1e-12`.
+const SMALLEST_PLAIN: f64 = 1e-6;
+
+/// A number with `precision` decimals if given, the shortest text that reads
+/// back as the same float otherwise, with an exponent if it is very large or
+/// very small. Never -0.
+fn number_text(x: f64, precision: Option<usize>) -> String {
+    let text = match precision {
+        Some(precision) => format!("{:.*}", precision, x),
+        None if x != 0.0 && (x.abs() >= LARGEST_PLAIN || x.abs() < SMALLEST_PLAIN) => {
+            format!("{:e}", x)
+        }
+        None => x.to_string(),
+    };
+    match text.strip_prefix('-') {
+        Some(digits) if digits.chars().all(|c| c == '0' || c == '.') => digits.to_string(),
+        _ => text,
+    }
+}
+
+/// Writes `text` padded to `width` with the fill and alignment of the
+/// format, numbers align right unless it says otherwise.
+fn pad(f: &mut fmt::Formatter, text: &str, width: usize) -> fmt::Result {
+    let padding = width.saturating_sub(text.chars().count());
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Left) => (0, padding),
+        Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+        _ => (padding, 0),
+    };
+    let fill = f.fill().to_string();
+    write!(f, "{}{}{}", fill.repeat(before), text, fill.repeat(after))
+}
+
+/// What print shows, without snapping small numbers to 0. Numbers take the
+/// width, precision and alignment of the format, other values are padded.
+/// With `{:#}` matrices and strings are written as literals that parse back.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.data {
+            ValueType::SCALAR(s) => {
+                let text = number_text(s.data, f.precision());
+                pad(f, &text, f.width().unwrap_or(0))
+            }
+            ValueType::MATRIX(m) => fmt::Display::fmt(m, f),
+            ValueType::TUPLE(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("\n\n")?;
                     }
-                    println!();
+                    fmt::Display::fmt(value, f)?;
                 }
+                Ok(())
+            }
+            ValueType::FUNCTION(function) => f.pad(&format!("<fn {}>", function.name.lexeme)),
+            ValueType::BUILTIN(builtin) => f.pad(&format!("<builtin {}>", builtin.name)),
+            ValueType::BOOL(b) => f.pad(if *b { "true" } else { "false" }),
+            ValueType::STRING(s) if f.alternate() => {
+                let escaped = s
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t");
+                f.pad(&format!("\"{}\"", escaped))
             }
+            ValueType::STRING(s) => f.pad(s),
+        }
+    }
+}
+
+/// Reads a literal as a script would write it: a number like `-2.5` or
+/// `0x1f`, `true`, `false`, a quoted string or a matrix like `[1 2 | 3 4]`.
+impl FromStr for Value {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        let mut lexer = Lexer::new_empty();
+        lexer.content = text.to_string();
+        Parser::new(lexer.scan_tokens())
+            .parse_value()
+            .map_err(|errors| errors[0].to_string())
+    }
+}
+
+/// Reads a matrix literal like `[1 2 | 3 4]`, the form `{:#}` writes.
+impl FromStr for Matrix {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, String> {
+        match text.parse::<Value>()?.data {
+            ValueType::MATRIX(matrix) => Ok(matrix),
+            _ => Err("Expected a matrix like [1 2 | 3 4]".to_string()),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(scalar: f64) -> Self {
+        Value::new_scalar(scalar)
+    }
+}
+
+/// A matrix from its rows, which must all be the same nonzero length.
+impl TryFrom<Vec<Vec<f64>>> for Value {
+    type Error = String;
+
+    fn try_from(rows: Vec<Vec<f64>>) -> Result<Self, String> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if cols == 0 {
+            return Err("A matrix needs at least one row and one column".to_string());
+        }
+        if rows.iter().any(|row| row.len() != cols) {
+            return Err("Every row of a matrix must have the same length".to_string());
+        }
+        let count = rows.len();
+        Ok(Value::new_matrix(rows.concat(), count, cols))
+    }
+}
+
+/// The rows of a matrix, a scalar is a 1x1 matrix.
+impl TryFrom<&Value> for Vec<Vec<f64>> {
+    type Error = String;
+
+    fn try_from(value: &Value) -> Result<Self, String> {
+        match &value.data {
+            // sliced by row count rather than chunked, which cannot take 0 columns
+            ValueType::MATRIX(m) => Ok((0..m.rows)
+                .map(|i| m.data[i * m.cols..(i + 1) * m.cols].to_vec())
+                .collect()),
+            ValueType::SCALAR(s) => Ok(vec![vec![s.data]]),
+            _ => Err(format!("Expected a matrix, not {}", Shape::of(value))),
         }
     }
 }
+
+impl TryFrom<Value> for Vec<Vec<f64>> {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, String> {
+        Vec::try_from(&value)
+    }
+}
@@ -0,0 +1,174 @@
+use std::io;
+use std::io::Write;
+
+use linear_algebra_ast::error::Error;
+use linear_algebra_ast::interpreter::Interpreter;
+use linear_algebra_ast::output::Capture;
+use linear_algebra_ast::value::Matrix;
+use linear_algebra_ast::value::Value;
+
+fn rows(value: Value) -> Vec<Vec<f64>> {
+    Vec::try_from(value).unwrap()
+}
+
+#[test]
+fn eval_str_returns_the_last_expression() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("1 + 2").unwrap(), Value::from(3.0));
+    assert_eq!(interpreter.eval_str("1 + 2;").unwrap(), Value::from(3.0));
+    assert_eq!(
+        rows(interpreter.eval_str("let A = [1 2 | 3 4];\nA^T").unwrap()),
+        vec![vec![1.0, 3.0], vec![2.0, 4.0]]
+    );
+    // anything else at the end has no value
+    assert_eq!(
+        interpreter.eval_str("let B = A;").unwrap(),
+        Value::wrap_tuple(Vec::new())
+    );
+}
+
+#[test]
+fn eval_str_still_needs_semicolons_between_statements() {
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(interpreter.eval_str("1 + 2 3"), Err(Error::Parse(_))));
+    assert!(matches!(interpreter.eval_str("let x = 1"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn eval_str_reports_each_kind_of_error() {
+    let mut interpreter = Interpreter::new();
+    let source = "[1 2] * [3 4]";
+    let error = interpreter.eval_str(source).unwrap_err();
+    assert!(matches!(error, Error::Analysis(_)));
+    assert_eq!(
+        error.report(source),
+        "Shape error at '*' (line 1, column 7): Cannot multiply 1×2 by 1×2\n\
+         1 | [1 2] * [3 4]\n  |       ^"
+    );
+    let error = interpreter.eval_str("inv([1 2 | 2 4])").unwrap_err();
+    assert!(matches!(error, Error::Runtime(_)));
+}
+
+#[test]
+fn printed_output_goes_to_the_output_sink() {
+    let mut interpreter = Interpreter::new();
+    let output = Capture::new();
+    interpreter.output = Box::new(output.clone());
+    interpreter.eval_str("print 1 + 1;").unwrap();
+    assert_eq!(output.contents(), "2\n");
+}
+
+#[test]
+fn shadow_warnings_go_to_the_diagnostics_sink() {
+    let mut interpreter = Interpreter::new();
+    let diagnostics = Capture::new();
+    interpreter.diagnostics = Box::new(diagnostics.clone());
+    interpreter.eval_str("let x = 1;\nlet x = 2;").unwrap();
+    assert_eq!(diagnostics.contents(), "");
+    interpreter.warn_shadowing = true;
+    assert_eq!(interpreter.eval_str("let x = 3;\nx").unwrap(), Value::from(3.0));
+    assert_eq!(
+        diagnostics.contents(),
+        "Warning at 'x' (line 1, column 5): x shadows an existing variable, use x = ... to change it\n\
+         1 | let x = 3;\n  |     ^\n"
+    );
+}
+
+/// A sink that refuses every write, like a closed pipe.
+struct Broken;
+
+impl Write for Broken {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn output_that_cannot_be_written_is_a_runtime_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.output = Box::new(Broken);
+    let source = "let x = 1;\nprint x;";
+    let error = interpreter.eval_str(source).unwrap_err();
+    assert!(matches!(error, Error::Runtime(_)));
+    assert_eq!(
+        error.to_string(),
+        "Runtime error at 'print' (line 2, column 1): Could not write the output: closed"
+    );
+    interpreter.explain = true;
+    let error = interpreter.eval_str("rref([1 2 | 3 4])").unwrap_err();
+    assert!(error.to_string().starts_with("Runtime error at 'rref'"));
+}
+
+#[test]
+fn set_and_get_share_variables_with_scripts() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .set("A", Value::try_from(vec![vec![2.0, 0.0], vec![0.0, 4.0]]).unwrap())
+        .unwrap();
+    interpreter.eval_str("let B = inv(A);").unwrap();
+    assert_eq!(
+        rows(interpreter.get("B").unwrap()),
+        vec![vec![0.5, 0.0], vec![0.0, 0.25]]
+    );
+    assert!(interpreter.get("C").is_none());
+    interpreter.eval_str("const k = 2;").unwrap();
+    assert!(interpreter.set("k", Value::from(3.0)).is_err());
+}
+
+#[test]
+fn conversions_check_the_shape_of_rows() {
+    assert!(Value::try_from(Vec::<Vec<f64>>::new()).is_err());
+    assert!(Value::try_from(vec![vec![]]).is_err());
+    assert!(Value::try_from(vec![vec![1.0, 2.0], vec![3.0]]).is_err());
+    assert_eq!(rows(Value::from(5.0)), vec![vec![5.0]]);
+    assert!(Vec::<Vec<f64>>::try_from(Value::new_bool(true)).is_err());
+}
+
+#[test]
+fn empty_matrices_convert_to_empty_rows() {
+    let mut interpreter = Interpreter::new();
+    let empty = interpreter.eval_str("[]").unwrap();
+    assert!(rows(empty).iter().all(Vec::is_empty));
+}
+
+/// What `{:#}` writes reads back as the same value.
+fn round_trip(value: Value) {
+    let text = format!("{:#}", value);
+    assert_eq!(text.parse::<Value>(), Ok(value), "{}", text);
+}
+
+#[test]
+fn literal_text_parses_back_to_the_same_value() {
+    round_trip(Value::from(-2.5));
+    round_trip(Value::from(0.1 + 0.2));
+    round_trip(Value::from(6.02e23));
+    round_trip(Value::from(-1.5e-12));
+    round_trip(Value::new_string("a \"quoted\"\n\tline".to_string()));
+    round_trip(Value::try_from(vec![vec![1.0, -2.0, 3.5]]).unwrap());
+    round_trip(Value::try_from(vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]).unwrap());
+    let matrix = "[1 2 | 3 4]".parse::<Matrix>().unwrap();
+    assert_eq!(format!("{:#}", Value::wrap_matrix(matrix)), "[1 2 | 3 4]");
+}
+
+#[test]
+fn matrix_text_with_an_empty_row_is_rejected() {
+    for text in ["[1 2 |]", "[| 1 2]", "[1 2 | 3]", "[1 2 3 | 4 5 6 7 8 9]"] {
+        assert!(text.parse::<Matrix>().is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn functions_from_earlier_calls_can_change_variable_shapes() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("fn reshape() { R = [1 2 3]; return 0; }\nlet R = [1 2 | 3 4];")
+        .unwrap();
+    assert_eq!(
+        rows(interpreter.eval_str("reshape();\nR * [1 | 1 | 1]").unwrap()),
+        vec![vec![6.0]]
+    );
+}
@@ -0,0 +1,40 @@
+//! Runs every script in tests/cases and compares what it prints with the
+//! .expected.txt next to it, which every script must have. Scripts that end
+//! in an error also have a .stderr.txt with the expected report, and have to
+//! exit with a failure status.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn cases_print_their_expected_output() {
+    let cases = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("cases");
+    let mut scripts: Vec<_> = fs::read_dir(&cases)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "la"))
+        .collect();
+    scripts.sort();
+    let mut failures = Vec::new();
+    for script in scripts {
+        let name = script.file_name().unwrap().to_string_lossy().into_owned();
+        let Ok(expected) = fs::read_to_string(script.with_extension("expected.txt")) else {
+            failures.push(format!("{} has no .expected.txt", name));
+            continue;
+        };
+        let expected_errors = fs::read_to_string(script.with_extension("stderr.txt")).ok();
+        let output = Command::new(env!("CARGO_BIN_EXE_linear-algebra-ast"))
+            .arg(&script)
+            .output()
+            .unwrap();
+        if String::from_utf8_lossy(&output.stdout) != expected
+            || String::from_utf8_lossy(&output.stderr) != expected_errors.as_deref().unwrap_or("")
+        {
+            failures.push(format!("{} printed something else", name));
+        } else if output.status.success() == expected_errors.is_some() {
+            failures.push(format!("{} exited with {}", name, output.status));
+        }
+    }
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
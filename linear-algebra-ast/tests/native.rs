@@ -0,0 +1,84 @@
+use std::ops::RangeInclusive;
+
+use linear_algebra_ast::interpreter::Interpreter;
+use linear_algebra_ast::native::Arguments;
+use linear_algebra_ast::native::NativeFunction;
+use linear_algebra_ast::value::Matrix;
+use linear_algebra_ast::value::Value;
+
+/// Scales a matrix, with a fixed number of arguments.
+struct Scaled;
+
+impl NativeFunction for Scaled {
+    fn call(&self, args: &Arguments) -> Result<Value, String> {
+        let factor: f64 = args.get(0)?;
+        let matrix: Matrix = args.get(1)?;
+        let rows = Vec::<Vec<f64>>::try_from(&Value::wrap_matrix(matrix))?;
+        let scaled = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(|x| x * factor).collect())
+            .collect::<Vec<Vec<f64>>>();
+        Value::try_from(scaled)
+    }
+
+    fn arity(&self) -> RangeInclusive<usize> {
+        2..=2
+    }
+
+    fn usage(&self, name: &str) -> String {
+        format!("{}(k, A)", name)
+    }
+}
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.register("double", |args| {
+        let x: f64 = args.get(0)?;
+        Ok(Value::from(2.0 * x))
+    });
+    interpreter.register_native("scaled", Scaled);
+    interpreter
+}
+
+fn error(source: &str) -> String {
+    interpreter().eval_str(source).unwrap_err().to_string()
+}
+
+#[test]
+fn registered_functions_are_called_from_scripts() {
+    let mut interpreter = interpreter();
+    assert_eq!(interpreter.eval_str("double(21) + 1").unwrap(), Value::from(43.0));
+    let value = interpreter.eval_str("scaled(2, [1 2 | 3 4]);").unwrap();
+    assert_eq!(
+        Vec::<Vec<f64>>::try_from(value),
+        Ok(vec![vec![2.0, 4.0], vec![6.0, 8.0]])
+    );
+}
+
+#[test]
+fn calls_with_the_wrong_number_of_arguments_show_the_usage() {
+    assert_eq!(
+        error("scaled(2);"),
+        "Shape error at 'scaled' (line 1, column 1): Expected a call of the form scaled(k, A)"
+    );
+}
+
+#[test]
+fn missing_arguments_are_reported_by_get() {
+    assert_eq!(
+        error("double();"),
+        "Runtime error at 'double' (line 1, column 1): double needs at least 1 argument"
+    );
+}
+
+#[test]
+fn arguments_of_the_wrong_type_are_reported_by_get() {
+    assert_eq!(
+        error("double([1 2]);"),
+        "Runtime error at 'double' (line 1, column 1): Argument 1 of double must be a scalar, not 1×2"
+    );
+    assert_eq!(
+        error("scaled(2, 3);"),
+        "Runtime error at 'scaled' (line 1, column 1): Argument 2 of scaled must be a matrix, not a scalar"
+    );
+}
@@ -0,0 +1,34 @@
+//! Feeds lines to the REPL on stdin, which ends the session when it runs out.
+
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+fn repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_linear-algebra-ast"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn open_blocks_and_doc_comments_continue_on_the_next_line() {
+    assert_eq!(
+        repl("fn two() {\n  return 2;\n}\n/// two again\nlet x = two();\nprint x;\n"),
+        "LA > ...  ...  LA > ...  LA > 2\nLA > "
+    );
+}
+
+#[test]
+fn braces_in_strings_and_comments_do_not_open_blocks() {
+    assert_eq!(
+        repl("let brace = \"{\"; # {\nprint 1;\n"),
+        "LA > LA > 1\nLA > "
+    );
+}
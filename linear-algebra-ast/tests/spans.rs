@@ -0,0 +1,29 @@
+use linear_algebra_ast::lexer::Lexer;
+use linear_algebra_ast::tokens::Token;
+use linear_algebra_ast::tokens::TokenType;
+
+fn lex(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new_empty();
+    lexer.content = source.to_string();
+    lexer.scan_tokens()
+}
+
+#[test]
+fn spans_are_byte_offsets_after_multi_byte_characters() {
+    let source = "let café = Δx + 1;";
+    for token in lex(source) {
+        if token.token_type == TokenType::TOKEN_EOF {
+            assert_eq!(token.span.start, source.len());
+            continue;
+        }
+        assert_eq!(&source[token.span.start..token.span.end], token.lexeme);
+    }
+}
+
+#[test]
+fn columns_count_characters() {
+    let tokens = lex("let café = Δx;");
+    let delta = tokens.iter().find(|token| token.lexeme == "Δx").unwrap();
+    assert_eq!(delta.span.column, 12);
+    assert_eq!(delta.span.start, "let café = ".len());
+}
@@ -14,25 +14,46 @@ ext = "la"
 exe_path = os.path.join("..", "target", "debug", "linear-algebra-ast.exe")
 
 ##
-# Runs a specific test case, comparing the output of the palladium file to the contents of the expected result file.
+# Runs a test case, returning what it wrote to stdout and stderr.
 #
 # @param case The name of the test case to run.
 #
-def run_test(case):
-    # Run the test case.
-    output = subprocess.check_output([
+def run_case(case):
+    result = subprocess.run([
         exe_path,
         os.path.join("cases", case + f".{ext}")
-    ])
+    ], capture_output=True)
+    return (result.stdout.decode("utf-8").replace("\r", ""),
+            result.stderr.decode("utf-8").replace("\r", ""),
+            result.returncode)
+
+
+##
+# Runs a specific test case, comparing the output of the palladium file to the contents of the expected result file.
+# Cases that end in an error also have a .stderr.txt file with the expected error and must exit with a failure.
+#
+# @param case The name of the test case to run.
+#
+def run_test(case):
+    expected_path = os.path.join("cases", case + ".expected.txt")
+    if not os.path.exists(expected_path):
+        print(f"Test [{case}] failed, it has no expected result file.")
+        return False
+    output, errors, status = run_case(case)
     # Compare the output to the expected result.
-    expected = open(os.path.join("cases", case + ".expected.txt"), "r")
-    passed = False
-    if output.decode("utf-8").replace("\r", "") == expected.read():
-        passed = True
+    with open(expected_path, "r") as expected:
+        passed = output == expected.read()
+    stderr_path = os.path.join("cases", case + ".stderr.txt")
+    expected_errors = ""
+    if os.path.exists(stderr_path):
+        with open(stderr_path, "r") as expected:
+            expected_errors = expected.read()
+    passed = passed and errors == expected_errors
+    passed = passed and (status != 0) == os.path.exists(stderr_path)
+    if passed:
         print(f"Test [{case}] passed.")
     else:
         print(f"Test [{case}] failed.")
-    expected.close()
 
     return passed
 
@@ -65,12 +86,12 @@ def compile_all_tests():
     for case in glob.glob(f"cases/*.{ext}"):
         case = os.path.splitext(os.path.os.path.basename(case))[0]
         print("\nCompiling test case: " + case)
+        output, errors, _ = run_case(case)
         with open(os.path.join("cases", case + ".expected.txt"), "w") as f:
-            output = subprocess.check_output([
-                exe_path,
-                os.path.join("cases", case + f".{ext}")
-            ])
-            f.write(output.decode("utf-8").replace("\r", ""))
+            f.write(output)
+        if errors:
+            with open(os.path.join("cases", case + ".stderr.txt"), "w") as f:
+                f.write(errors)
         counter += 1
 
     print(f"\nSuccessfully compiled {counter} test cases")